@@ -119,6 +119,32 @@ where
     Ok(millis.map(Duration::from_secs))
 }
 
+/// Deserialize an optional number of seconds from any BSON number type. The server reports
+/// `NaN` or values too large to represent for TTL indexes that should effectively never expire,
+/// so these are clamped to the largest number of seconds that can be serialized back as an `i64`.
+pub(crate) fn deserialize_duration_option_from_bson_number_seconds<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let secs = match Option::<Bson>::deserialize(deserializer)? {
+        Some(bson) => bson,
+        None => return Ok(None),
+    };
+
+    let max = i64::MAX as u64;
+    let secs = match secs {
+        Bson::Double(f) if f.is_nan() || f >= max as f64 => max,
+        Bson::Double(f) if f >= 0.0 => f as u64,
+        ref other => get_u64(other).ok_or_else(|| {
+            D::Error::custom(format!("could not deserialize seconds from {:?}", other))
+        })?,
+    };
+
+    Ok(Some(Duration::from_secs(secs)))
+}
+
 #[allow(clippy::trivially_copy_pass_by_ref)]
 pub(crate) fn serialize_u32_option_as_i32<S: Serializer>(
     val: &Option<u32>,
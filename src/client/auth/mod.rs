@@ -37,7 +37,7 @@ const PLAIN_STR: &str = "PLAIN";
 /// The authentication mechanisms supported by MongoDB.
 ///
 /// Note: not all of these mechanisms are currently supported by the driver.
-#[derive(Clone, Deserialize, PartialEq, Debug)]
+#[derive(Clone, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[non_exhaustive]
 pub enum AuthMechanism {
     /// MongoDB Challenge Response nonce and MD5 based authentication system. It is currently
@@ -371,7 +371,13 @@ impl Credential {
         doc
     }
 
+    /// The database to authenticate against: the explicitly configured source if there is one,
+    /// and the mechanism's default source otherwise.
     pub(crate) fn resolved_source(&self) -> &str {
+        if let Some(ref source) = self.source {
+            return source;
+        }
+
         self.mechanism
             .as_ref()
             .map(|m| m.default_source(None))
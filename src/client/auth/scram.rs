@@ -51,14 +51,29 @@ lazy_static! {
     };
 }
 
+/// The key used to look up salted passwords in the credential cache. The username is included
+/// because SCRAM-SHA-1 derives the salted password from both the username and the password.
 #[derive(Hash, Eq, PartialEq)]
 struct CacheEntry {
+    username: String,
     password: String,
     salt: Vec<u8>,
     i: u32,
     mechanism: ScramVersion,
 }
 
+impl CacheEntry {
+    fn new(username: &str, password: &str, i: u32, salt: &[u8], mechanism: &ScramVersion) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            salt: salt.to_vec(),
+            i,
+            mechanism: mechanism.clone(),
+        }
+    }
+}
+
 /// The versions of SCRAM supported by the driver (classified according to hash function used).
 #[derive(Debug, Hash, Eq, PartialEq, Clone)]
 pub(crate) enum ScramVersion {
@@ -183,25 +198,9 @@ impl ScramVersion {
         let server_first = ServerFirst::parse(server_first)?;
         server_first.validate(client_first.nonce.as_str())?;
 
-        let cache_entry_key = CacheEntry {
-            password: password.to_string(),
-            salt: server_first.salt().to_vec(),
-            i: server_first.i(),
-            mechanism: self.clone(),
-        };
-        let (should_update_cache, salted_password) =
-            match CREDENTIAL_CACHE.read().await.get(&cache_entry_key) {
-                Some(pwd) => (false, pwd.clone()),
-                None => (
-                    true,
-                    self.compute_salted_password(
-                        username,
-                        password,
-                        server_first.i(),
-                        server_first.salt(),
-                    )?,
-                ),
-            };
+        let (should_update_cache, salted_password) = self
+            .cached_salted_password(username, password, server_first.i(), server_first.salt())
+            .await?;
 
         let client_final = ClientFinal::new(
             source,
@@ -257,15 +256,55 @@ impl ScramVersion {
         }
 
         if should_update_cache {
-            let mut cache = CREDENTIAL_CACHE.write().await;
-            if let Entry::Vacant(entry) = cache.entry(cache_entry_key) {
-                entry.insert(salted_password);
-            }
+            self.cache_salted_password(
+                username,
+                password,
+                server_first.i(),
+                server_first.salt(),
+                salted_password,
+            )
+            .await;
         }
 
         Ok(())
     }
 
+    /// Looks up the salted password for the given parameters in the credential cache, computing it
+    /// if it is not present. The returned boolean indicates whether the password had to be
+    /// computed and thus should be added to the cache once authentication succeeds.
+    async fn cached_salted_password(
+        &self,
+        username: &str,
+        password: &str,
+        i: u32,
+        salt: &[u8],
+    ) -> Result<(bool, Vec<u8>)> {
+        let cache_entry_key = CacheEntry::new(username, password, i, salt, self);
+
+        if let Some(salted_password) = CREDENTIAL_CACHE.read().await.get(&cache_entry_key) {
+            return Ok((false, salted_password.clone()));
+        }
+
+        Ok((true, self.compute_salted_password(username, password, i, salt)?))
+    }
+
+    /// Stores a salted password in the credential cache so that subsequent handshakes with the
+    /// same parameters can skip recomputing it.
+    async fn cache_salted_password(
+        &self,
+        username: &str,
+        password: &str,
+        i: u32,
+        salt: &[u8],
+        salted_password: Vec<u8>,
+    ) {
+        let mut cache = CREDENTIAL_CACHE.write().await;
+        let key = CacheEntry::new(username, password, i, salt, self);
+        if let Entry::Vacant(entry) = cache.entry(key) {
+            entry.insert(salted_password);
+        }
+    }
+
     /// HMAC function used as part of SCRAM authentication.
     fn hmac(&self, key: &[u8], input: &[u8]) -> Result<Vec<u8>> {
         let bytes = match self {
@@ -718,7 +757,7 @@ impl ServerFinal {
 mod tests {
     use crate::bson::Bson;
 
-    use super::ServerFirst;
+    use super::{ScramVersion, ServerFirst};
 
     #[cfg_attr(feature = "tokio-runtime", tokio::test)]
     #[cfg_attr(feature = "async-std-runtime", async_std::test)]
@@ -741,4 +780,50 @@ mod tests {
         };
         assert!(valid_iteration_count.validate(nonce).is_ok())
     }
+
+    #[cfg_attr(feature = "tokio-runtime", tokio::test)]
+    #[cfg_attr(feature = "async-std-runtime", async_std::test)]
+    async fn salted_password_cached_across_handshakes() {
+        let salt = b"salted password cache test";
+
+        for scram in &[ScramVersion::Sha1, ScramVersion::Sha256] {
+            let (should_update_cache, salted_password) = scram
+                .cached_salted_password("cache_user", "pencil", 4096, salt)
+                .await
+                .unwrap();
+            assert!(should_update_cache);
+
+            scram
+                .cache_salted_password("cache_user", "pencil", 4096, salt, salted_password.clone())
+                .await;
+
+            for _ in 0..3 {
+                let (should_update_cache, cached) = scram
+                    .cached_salted_password("cache_user", "pencil", 4096, salt)
+                    .await
+                    .unwrap();
+                assert!(!should_update_cache);
+                assert_eq!(cached, salted_password);
+            }
+
+            // A different user, iteration count, or salt requires a new derivation.
+            let (should_update_cache, _) = scram
+                .cached_salted_password("other_user", "pencil", 4096, salt)
+                .await
+                .unwrap();
+            assert!(should_update_cache);
+
+            let (should_update_cache, _) = scram
+                .cached_salted_password("cache_user", "pencil", 8192, salt)
+                .await
+                .unwrap();
+            assert!(should_update_cache);
+
+            let (should_update_cache, _) = scram
+                .cached_salted_password("cache_user", "pencil", 4096, b"other salt")
+                .await
+                .unwrap();
+            assert!(should_update_cache);
+        }
+    }
 }
@@ -22,6 +22,18 @@ async fn negotiate_both_scram() {
         AuthMechanism::from_stream_description(&description_both),
         AuthMechanism::ScramSha256
     );
+
+    // The order the server lists the mechanisms in doesn't matter.
+    let mut reversed = MECHS.to_vec();
+    reversed.reverse();
+    let description_reversed = StreamDescription {
+        sasl_supported_mechs: Some(reversed),
+        ..Default::default()
+    };
+    assert_eq!(
+        AuthMechanism::from_stream_description(&description_reversed),
+        AuthMechanism::ScramSha256
+    );
 }
 
 #[cfg_attr(feature = "tokio-runtime", tokio::test)]
@@ -29,7 +29,6 @@ use crate::{
     sdam::{
         HandshakePhase,
         SelectedServer,
-        ServerType,
         SessionSupportStatus,
         TransactionSupportStatus,
     },
@@ -160,7 +159,10 @@ impl Client {
             }
         };
 
-        let mut conn = match server.pool.check_out().await {
+        let mut conn = match server.pool.check_out().await.and_then(|conn| {
+            server.pool.check_generation(&conn)?;
+            Ok(conn)
+        }) {
             Ok(conn) => conn,
             Err(mut err) => {
                 err.add_labels_and_update_pin(None, &mut session, None)?;
@@ -255,7 +257,10 @@ impl Client {
             }
         };
 
-        let mut conn = match server.pool.check_out().await {
+        let mut conn = match server.pool.check_out().await.and_then(|conn| {
+            server.pool.check_generation(&conn)?;
+            Ok(conn)
+        }) {
             Ok(c) => c,
             Err(_) => return Err(first_error),
         };
@@ -304,7 +309,7 @@ impl Client {
         }
 
         let stream_description = connection.stream_description()?;
-        let is_sharded = stream_description.initial_server_type == ServerType::Mongos;
+        let is_sharded = stream_description.is_mongos;
         let mut cmd = op.build(stream_description)?;
         self.inner
             .topology
@@ -614,7 +619,7 @@ impl Client {
     }
 
     /// Returns the retryability level for the execution of this operation.
-    async fn get_retryability<T: Operation>(
+    pub(super) async fn get_retryability<T: Operation>(
         &self,
         conn: &Connection,
         op: &T,
@@ -2,17 +2,21 @@ pub mod auth;
 mod executor;
 pub mod options;
 pub mod session;
+#[cfg(test)]
+mod test;
 
 use std::{sync::Arc, time::Duration};
 
 use bson::Bson;
 use derivative::Derivative;
+use futures_core::Stream;
+use futures_util::{stream, StreamExt};
 use std::time::Instant;
 
 #[cfg(test)]
 use crate::options::ServerAddress;
 use crate::{
-    bson::Document,
+    bson::{doc, Document},
     concern::{ReadConcern, WriteConcern},
     db::Database,
     error::{ErrorKind, Result},
@@ -29,6 +33,8 @@ use crate::{
     results::DatabaseSpecification,
     sdam::{SelectedServer, SessionSupportStatus, Topology},
     ClientSession,
+    IndexModel,
+    Namespace,
 };
 pub(crate) use executor::{HELLO_COMMAND_NAMES, REDACTED_COMMANDS};
 pub(crate) use session::{ClusterTime, SESSIONS_UNSUPPORTED_COMMANDS};
@@ -37,6 +43,9 @@ use session::{ServerSession, ServerSessionPool};
 
 const DEFAULT_SERVER_SELECTION_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// The default number of databases or collections `Client::list_all_indexes` lists at once.
+const DEFAULT_LIST_ALL_INDEXES_CONCURRENCY: usize = 4;
+
 /// This is the main entry point for the API. A `Client` is used to connect to a MongoDB cluster.
 /// By default, it will monitor the topology of the cluster, keeping track of any changes, such
 /// as servers being added or removed.
@@ -220,6 +229,81 @@ impl Client {
         }
     }
 
+    /// Lists the indexes of every collection in every database in the cluster the Client is
+    /// connected to, e.g. to audit the indexes of a whole deployment. Views are skipped, as they
+    /// have no indexes of their own.
+    ///
+    /// At most `max_concurrency` databases or collections (4 by default) are listed at once, and
+    /// more are only listed as the stream is polled. An error listing the collections of one
+    /// database or the indexes of one collection is yielded as an item of the stream, and the
+    /// remaining namespaces are still listed.
+    pub fn list_all_indexes(
+        &self,
+        max_concurrency: impl Into<Option<usize>>,
+    ) -> impl Stream<Item = Result<(Namespace, IndexModel)>> + Send + 'static {
+        let max_concurrency = max_concurrency
+            .into()
+            .unwrap_or(DEFAULT_LIST_ALL_INDEXES_CONCURRENCY)
+            .max(1);
+        let client = self.clone();
+        let collections_client = self.clone();
+        let indexes_client = self.clone();
+
+        let databases = async move {
+            match client.list_database_names(None, None).await {
+                Ok(names) => names.into_iter().map(Ok).collect(),
+                Err(error) => vec![Err(error)],
+            }
+        };
+
+        stream::once(databases)
+            .flat_map(stream::iter)
+            .map(move |db: Result<String>| {
+                let client = collections_client.clone();
+                async move {
+                    let db = match db {
+                        Ok(db) => db,
+                        Err(error) => return vec![Err(error)],
+                    };
+                    let filter = doc! { "type": "collection" };
+                    match client.database(&db).list_collection_names(filter).await {
+                        Ok(names) => names
+                            .into_iter()
+                            .map(|coll| {
+                                Ok(Namespace {
+                                    db: db.clone(),
+                                    coll,
+                                })
+                            })
+                            .collect(),
+                        Err(error) => vec![Err(error)],
+                    }
+                }
+            })
+            .buffered(max_concurrency)
+            .flat_map(stream::iter)
+            .map(move |ns: Result<Namespace>| {
+                let client = indexes_client.clone();
+                async move {
+                    let ns = match ns {
+                        Ok(ns) => ns,
+                        Err(error) => return vec![Err(error)],
+                    };
+                    let coll = client.database(&ns.db).collection::<Document>(&ns.coll);
+                    let cursor = match coll.list_indexes(None).await {
+                        Ok(cursor) => cursor,
+                        Err(error) => return vec![Err(error)],
+                    };
+                    cursor
+                        .map(|index| index.map(|index| (ns.clone(), index)))
+                        .collect()
+                        .await
+                }
+            })
+            .buffered(max_concurrency)
+            .flat_map(stream::iter)
+    }
+
     /// Starts a new `ClientSession`.
     pub async fn start_session(&self, options: Option<SessionOptions>) -> Result<ClientSession> {
         match self.get_session_support_status().await? {
@@ -232,6 +316,16 @@ impl Client {
         }
     }
 
+    /// Returns a warning if the mongoses the Client is connected to report differing max wire
+    /// versions, e.g. partway through upgrading a sharded cluster. The features available to an
+    /// operation may then depend on which mongos it's routed to.
+    ///
+    /// The warning reflects the mongoses the Client has connected to so far, and is `None` for
+    /// topologies other than sharded clusters.
+    pub async fn mongos_version_mismatch(&self) -> Option<String> {
+        self.inner.topology.mongos_version_mismatch().await
+    }
+
     /// Check in a server session to the server session pool.
     /// If the session is expired or dirty, or the topology no longer supports sessions, the session
     /// will be discarded.
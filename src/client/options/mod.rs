@@ -21,6 +21,7 @@ use lazy_static::lazy_static;
 use rustls::{
     internal::pemfile,
     Certificate,
+    ProtocolVersion,
     RootCertStore,
     ServerCertVerified,
     ServerCertVerifier,
@@ -41,15 +42,29 @@ use crate::{
     bson::{doc, Bson, Document},
     bson_util,
     client::auth::{AuthMechanism, Credential},
+    cmap::HandshakeReplyCache,
     concern::{Acknowledgment, ReadConcern, WriteConcern},
     error::{ErrorKind, Result},
-    event::{cmap::CmapEventHandler, command::CommandEventHandler},
+    event::{cmap::CmapEventHandler, command::CommandEventHandler, sdam::SdamEventHandler},
     options::ReadConcernLevel,
     sdam::MIN_HEARTBEAT_FREQUENCY,
     selection_criteria::{ReadPreference, SelectionCriteria, TagSet},
     srv::{OriginalSrvInfo, SrvResolver},
 };
 
+pub use crate::cmap::{
+    AppMetadata,
+    AppNameSanitizer,
+    CertMatchesSet,
+    ClientMetadata,
+    ContainerMetadata,
+    DriverMetadata,
+    EnvMetadata,
+    HandshakeCallback,
+    HandshakeSummary,
+    MetadataEncoder,
+    OsMetadata,
+};
 pub use resolver_config::ResolverConfig;
 
 const DEFAULT_PORT: u16 = 27017;
@@ -65,6 +80,7 @@ const URI_OPTIONS: &[&str] = &[
     "heartbeatfrequencyms",
     "journal",
     "localthresholdms",
+    "maxconnecting",
     "maxidletimems",
     "maxstalenessseconds",
     "maxpoolsize",
@@ -403,6 +419,13 @@ pub struct ClientOptions {
     #[serde(skip)]
     pub command_event_handler: Option<Arc<dyn CommandEventHandler>>,
 
+    /// The handler that should process all Server Discovery and Monitoring events. See the
+    /// SdamEventHandler type documentation for more details.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[builder(default)]
+    #[serde(skip)]
+    pub sdam_event_handler: Option<Arc<dyn SdamEventHandler>>,
+
     /// The connect timeout passed to each underlying TcpStream when attemtping to connect to the
     /// server.
     ///
@@ -426,6 +449,14 @@ pub struct ClientOptions {
     #[builder(default)]
     pub driver_info: Option<DriverInfo>,
 
+    /// The base driver version to report in the metadata of the handshake with the server, before
+    /// any version from `driver_info` is appended. This can be used to pin the reported version to
+    /// a release tag for reproducible builds.
+    ///
+    /// The default value is the version of this crate.
+    #[builder(default)]
+    pub driver_version: Option<String>,
+
     /// The amount of time each monitoring thread should wait between sending an isMaster command
     /// to its respective server.
     ///
@@ -454,6 +485,14 @@ pub struct ClientOptions {
     #[builder(default)]
     pub max_idle_time: Option<Duration>,
 
+    /// The maximum number of connections that a connection pool may be establishing concurrently.
+    /// Operations that need a new connection while `max_connecting` connections are being
+    /// established wait for one of them to finish. This must be at least 1.
+    ///
+    /// The default value is 2.
+    #[builder(default)]
+    pub max_connecting: Option<u32>,
+
     /// The maximum amount of connections that the Client should allow to be created in a
     /// connection pool for a given server. If an operation is attempted on a server while
     /// `max_pool_size` connections are checked out, the operation will block until an in-progress
@@ -532,9 +571,201 @@ pub struct ClientOptions {
     #[builder(default)]
     pub write_concern: Option<WriteConcern>,
 
+    /// Whether connecting to a server whose wire version does not support the configured
+    /// `write_concern` should cause the connection to fail. If false, the write concern is not
+    /// checked when connecting, and any incompatibility surfaces when a write is attempted.
+    ///
+    /// The default value is false.
+    #[builder(default)]
+    pub strict_write_concern: Option<bool>,
+
+    /// The minimum wire version a server must support for the Client to connect to it. Servers
+    /// whose maximum wire version is below this one are rejected when connecting with an
+    /// `ErrorKind::IncompatibleServer` error, e.g. to enforce a policy that only MongoDB 4.2
+    /// (wire version 8) and newer may be used.
+    ///
+    /// By default, only the driver's own minimum supported wire version is enforced.
+    #[builder(default)]
+    pub min_server_wire_version: Option<i32>,
+
+    /// Whether to restrict authentication to FIPS-approved mechanisms. SCRAM-SHA-1 relies on SHA-1,
+    /// which is not approved, so connecting fails with an authentication error if the credential
+    /// specifies SCRAM-SHA-1 or if mechanism negotiation would select it.
+    ///
+    /// The default value is false.
+    #[builder(default)]
+    pub fips_mode: Option<bool>,
+
+    /// The maximum time to wait for the server to reply to the handshake of a new connection.
+    /// Unlike `connect_timeout`, this covers the handshake itself rather than opening the socket.
+    /// If it elapses, connecting fails with a network timeout error.
+    ///
+    /// By default, the handshake has no timeout of its own.
+    #[builder(default)]
+    pub handshake_timeout: Option<Duration>,
+
+    /// The maximum time to wait for a new connection to authenticate after its handshake, including
+    /// any remaining SASL conversation with the server. Timing out authentication separately from
+    /// `handshake_timeout` shows whether server discovery or authentication is slow. If it elapses,
+    /// connecting fails with a network timeout error.
+    ///
+    /// By default, authentication has no timeout of its own.
+    #[builder(default)]
+    pub auth_timeout: Option<Duration>,
+
+    /// Whether to include the name of the machine the driver is running on in the metadata sent to
+    /// the server when connecting, under the `x-hostname` field, e.g. for identifying clients in
+    /// server logs. It is the first field left out if the metadata would exceed the size limit.
+    ///
+    /// The default value is false.
+    #[builder(default)]
+    pub include_hostname: Option<bool>,
+
+    /// Stops the Client from handshaking with a server that keeps failing handshakes, if set. See
+    /// [`CircuitBreakerOptions`](struct.CircuitBreakerOptions.html) for more details.
+    ///
+    /// By default, there is no circuit breaker.
+    #[builder(default)]
+    #[serde(skip)]
+    pub circuit_breaker: Option<CircuitBreakerOptions>,
+
+    /// The version of the framework the application is built on, e.g. `2.3`, which the Client sends
+    /// to the server as part of the handshake along with `app_name`, e.g. to correlate connections
+    /// with framework upgrades. It is only sent if `app_name` is also set, and it is left out
+    /// before any other field except the hostname if the handshake metadata would exceed the size
+    /// limit.
+    #[builder(default)]
+    pub framework_version: Option<String>,
+
+    /// Whether to leave the `platform` field, which describes the Rust compiler and async runtime
+    /// the driver was built with, out of the metadata sent to the server when connecting,
+    /// including any platform given in `driver_info`.
+    ///
+    /// The default value is false.
+    #[builder(default)]
+    pub omit_platform: Option<bool>,
+
+    /// The request id to use for the wire message carrying the handshake of each new connection,
+    /// e.g. so that handshakes can be correlated with the logs of a proxy that records request ids.
+    ///
+    /// By default, each handshake is sent with the next auto-incremented request id.
+    #[builder(default)]
+    pub handshake_request_id: Option<i32>,
+
+    /// Invoked with a summary of each handshake the Client's connection pools complete, for
+    /// lightweight instrumentation that doesn't require subscribing to CMAP events.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[builder(default)]
+    #[serde(skip)]
+    pub on_handshake_complete: Option<HandshakeCallback>,
+
+    /// How long the reply a server sent to the handshake of one connection may be reused to
+    /// initialize new connections to the same server, skipping the handshake round trip, e.g. when
+    /// several connections are opened in quick succession. Connections initialized this way still
+    /// authenticate in full, but skip speculative authentication.
+    ///
+    /// By default, every connection runs its own handshake.
+    #[builder(default)]
+    pub handshake_reply_cache_window: Option<Duration>,
+
+    /// A document to send verbatim as the `client` metadata of each handshake, replacing the
+    /// metadata the driver would otherwise assemble, including the driver name and version and any
+    /// `app_name` or `driver_info`.
+    ///
+    /// This is dangerous: servers log and validate this document, and an incorrect one can make
+    /// the client misidentify itself or cause handshakes to be rejected. It is only intended for
+    /// compatibility testing. The document is not checked for the fields the handshake spec
+    /// requires.
+    #[builder(default)]
+    pub handshake_metadata_override: Option<Document>,
+
+    /// The database to send each handshake to, replacing the auth source of `credential`, e.g. for
+    /// a gateway that routes connections to tenants by database.
+    ///
+    /// This only changes where the `isMaster` command is sent. Authentication, including the
+    /// `saslSupportedMechs` negotiation and any speculative authentication in the handshake, still
+    /// uses the credential's source; to authenticate against a different database, set the
+    /// credential's `source` instead.
+    #[builder(default)]
+    pub handshake_target_db: Option<String>,
+
+    /// Verifies the certificate presented by each replica set member against the set name the
+    /// member reported in its handshake, as a defense-in-depth check on top of TLS certificate
+    /// validation. A mismatch fails the handshake with an authentication error. Members that report
+    /// no set name, and connections that don't use TLS, are not checked.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[builder(default)]
+    #[serde(skip)]
+    pub cert_matches_set: Option<CertMatchesSet>,
+
+    /// Whether to check that the driver name in the handshake metadata still starts with
+    /// `mongo-rust-driver` once any `driver_info` or `handshake_metadata_override` has been
+    /// applied, which catches wrapping libraries that replace the driver name rather than appending
+    /// to it. If the check fails, every handshake fails with an invalid argument error.
+    ///
+    /// The default value is false.
+    #[builder(default)]
+    pub validate_driver_name: Option<bool>,
+
+    /// Encodes the client metadata sent in each handshake, replacing the default encoding of every
+    /// field, e.g. to drop optional fields in size-constrained environments. Unlike with
+    /// `handshake_metadata_override`, the metadata is still assembled by the driver, but the
+    /// encoder decides what to send. An encoder may omit fields the handshake spec requires, so
+    /// its output is not checked.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[builder(default)]
+    #[serde(skip)]
+    pub metadata_encoder: Option<Arc<dyn MetadataEncoder>>,
+
+    /// The authentication mechanisms to attempt speculative authentication for, e.g. to keep X.509
+    /// authentication out of the handshake so it is audited separately. When set, a credential
+    /// using any other mechanism is authenticated in full after the handshake instead.
+    ///
+    /// By default, speculative authentication is attempted for every mechanism that supports it.
+    #[builder(default)]
+    pub speculative_auth_mechanisms: Option<HashSet<AuthMechanism>>,
+
+    /// The hostname to present via SNI when establishing TLS connections, in place of the host
+    /// being connected to, e.g. so that a TLS-terminating proxy routes connections correctly.
+    /// Certificates are also verified against this hostname.
+    ///
+    /// The default is to present the host being connected to.
+    #[builder(default)]
+    pub tls_sni_override: Option<String>,
+
+    /// Fields to add to the body of each handshake command, outside of the `client` metadata, e.g.
+    /// to experiment with a server build that accepts a new handshake field. Fields the driver sets
+    /// itself, such as `client` or `speculativeAuthenticate`, and fields starting with `$` are
+    /// ignored.
+    #[builder(default)]
+    pub handshake_extra_fields: Option<Document>,
+
+    /// The oldest TLS protocol version connections may use, e.g. `TLSv1_2` for compliance
+    /// requirements. A TLS connection that negotiated an older version fails its handshake with an
+    /// authentication error before anything is sent over it. Connections that don't use TLS are not
+    /// checked.
+    #[builder(default)]
+    #[serde(skip)]
+    pub min_tls_version: Option<ProtocolVersion>,
+
+    /// Transforms `app_name` before it is sent in the handshake metadata, e.g. to remove personal
+    /// information that shouldn't reach the server. Control characters are always removed from the
+    /// name before the sanitizer is applied, whether or not one is configured.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[builder(default)]
+    #[serde(skip)]
+    pub app_name_sanitizer: Option<AppNameSanitizer>,
+
     #[builder(default, setter(skip))]
     pub(crate) zlib_compression: Option<i32>,
 
+    /// The cache of handshake replies shared by the monitors and connection pools of the Client,
+    /// created from `handshake_reply_cache_window` when the Client's topology is created.
+    #[builder(default, setter(skip))]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) handshake_reply_cache: Option<HandshakeReplyCache>,
+
     /// Information from the SRV URI that generated these client options, if applicable.
     #[builder(default, setter(skip))]
     #[serde(skip)]
@@ -606,6 +837,8 @@ impl Serialize for ClientOptions {
             #[serde(serialize_with = "bson_util::serialize_duration_option_as_int_millis")]
             maxidletimems: &'a Option<Duration>,
 
+            maxconnecting: &'a Option<u32>,
+
             maxpoolsize: &'a Option<u32>,
 
             minpoolsize: &'a Option<u32>,
@@ -649,6 +882,7 @@ impl Serialize for ClientOptions {
             heartbeatfrequencyms: &self.heartbeat_freq,
             localthresholdms: &self.local_threshold,
             maxidletimems: &self.max_idle_time,
+            maxconnecting: &self.max_connecting,
             maxpoolsize: &self.max_pool_size,
             minpoolsize: &self.min_pool_size,
             readconcern: &self.read_concern,
@@ -683,6 +917,7 @@ struct ClientOptionsParser {
     pub max_pool_size: Option<u32>,
     pub min_pool_size: Option<u32>,
     pub max_idle_time: Option<Duration>,
+    pub max_connecting: Option<u32>,
     pub wait_queue_timeout: Option<Duration>,
     pub compressors: Option<Vec<String>>,
     pub connect_timeout: Option<Duration>,
@@ -899,6 +1134,24 @@ pub struct DriverInfo {
     pub platform: Option<String>,
 }
 
+/// Configures a circuit breaker that stops the Client from handshaking with a server after
+/// repeated handshake failures, so that connection attempts aren't wasted on a server that is
+/// failing. While the breaker is open, checking out a connection to the server fails immediately;
+/// once `cool_down` has passed, a single trial handshake is let through, and the breaker closes
+/// again if it succeeds.
+#[derive(Clone, Debug, TypedBuilder, PartialEq)]
+#[non_exhaustive]
+pub struct CircuitBreakerOptions {
+    /// The number of consecutive handshake failures within `window` that opens the breaker.
+    pub failure_threshold: u32,
+
+    /// The window in which consecutive failures are counted.
+    pub window: Duration,
+
+    /// How long the breaker stays open before a single trial handshake is let through.
+    pub cool_down: Duration,
+}
+
 impl From<ClientOptionsParser> for ClientOptions {
     fn from(parser: ClientOptionsParser) -> Self {
         Self {
@@ -912,6 +1165,7 @@ impl From<ClientOptionsParser> for ClientOptions {
             repl_set_name: parser.repl_set_name,
             write_concern: parser.write_concern,
             max_pool_size: parser.max_pool_size,
+            max_connecting: parser.max_connecting,
             min_pool_size: parser.min_pool_size,
             max_idle_time: parser.max_idle_time,
             server_selection_timeout: parser.server_selection_timeout,
@@ -923,13 +1177,38 @@ impl From<ClientOptionsParser> for ClientOptions {
             zlib_compression: parser.zlib_compression,
             direct_connection: parser.direct_connection,
             driver_info: None,
+            driver_version: None,
             credential: parser.credential,
             cmap_event_handler: None,
             command_event_handler: None,
+            sdam_event_handler: None,
             original_srv_info: None,
             original_uri: Some(parser.original_uri),
             resolver_config: None,
             server_api: None,
+            strict_write_concern: None,
+            min_server_wire_version: None,
+            fips_mode: None,
+            handshake_timeout: None,
+            auth_timeout: None,
+            include_hostname: None,
+            circuit_breaker: None,
+            framework_version: None,
+            omit_platform: None,
+            handshake_request_id: None,
+            handshake_reply_cache_window: None,
+            handshake_reply_cache: None,
+            on_handshake_complete: None,
+            handshake_metadata_override: None,
+            handshake_target_db: None,
+            cert_matches_set: None,
+            validate_driver_name: None,
+            metadata_encoder: None,
+            speculative_auth_mechanisms: None,
+            tls_sni_override: None,
+            handshake_extra_fields: None,
+            min_tls_version: None,
+            app_name_sanitizer: None,
             #[cfg(test)]
             heartbeat_freq_test: None,
             allow_load_balanced: false,
@@ -973,6 +1252,7 @@ impl ClientOptions {
     ///   * `heartbeatFrequencyMS`: maps to the `heartbeat_frequency` field
     ///   * `journal`: maps to the `journal` field of the `write_concern` field
     ///   * `localThresholdMS`: maps to the `local_threshold` field
+    ///   * `maxConnecting`: maps to the `max_connecting` field
     ///   * `maxIdleTimeMS`: maps to the `max_idle_time` field
     ///   * `maxStalenessSeconds`: maps to the `max_staleness` field of the `selection_criteria`
     ///     field
@@ -1140,6 +1420,23 @@ impl ClientOptions {
             write_concern.validate()?;
         }
 
+        // A pool that can't establish any connections would block every checkout forever.
+        if self.max_connecting == Some(0) {
+            return Err(ErrorKind::InvalidArgument {
+                message: "maxConnecting must be at least 1".to_string(),
+            }
+            .into());
+        }
+
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            if circuit_breaker.failure_threshold == 0 {
+                return Err(ErrorKind::InvalidArgument {
+                    message: "circuit breaker failure_threshold must be at least 1".to_string(),
+                }
+                .into());
+            }
+        }
+
         if !self.allow_load_balanced && self.load_balanced.is_some() {
             return Err(ErrorKind::InvalidArgument {
                 message: "loadBalanced is not supported".to_string(),
@@ -1180,6 +1477,10 @@ impl ClientOptions {
             self,
             [
                 app_name,
+                app_name_sanitizer,
+                auth_timeout,
+                cert_matches_set,
+                circuit_breaker,
                 compressors,
                 cmap_event_handler,
                 command_event_handler,
@@ -1187,20 +1488,41 @@ impl ClientOptions {
                 credential,
                 direct_connection,
                 driver_info,
+                driver_version,
+                fips_mode,
+                framework_version,
+                handshake_extra_fields,
+                handshake_metadata_override,
+                handshake_reply_cache_window,
+                handshake_request_id,
+                handshake_target_db,
+                handshake_timeout,
                 heartbeat_freq,
+                include_hostname,
                 local_threshold,
+                max_connecting,
                 max_idle_time,
                 max_pool_size,
+                metadata_encoder,
                 min_pool_size,
+                min_server_wire_version,
+                min_tls_version,
+                omit_platform,
+                on_handshake_complete,
                 read_concern,
                 repl_set_name,
                 retry_reads,
                 retry_writes,
+                sdam_event_handler,
                 selection_criteria,
                 server_api,
                 server_selection_timeout,
                 socket_timeout,
+                speculative_auth_mechanisms,
+                strict_write_concern,
                 tls,
+                tls_sni_override,
+                validate_driver_name,
                 write_concern,
                 zlib_compression,
                 original_srv_info,
@@ -1747,6 +2069,9 @@ impl ClientOptionsParser {
 
                 self.max_staleness = Some(max_staleness);
             }
+            k @ "maxconnecting" => {
+                self.max_connecting = Some(get_u32!(value, k));
+            }
             k @ "maxpoolsize" => {
                 self.max_pool_size = Some(get_u32!(value, k));
             }
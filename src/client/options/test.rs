@@ -1,9 +1,11 @@
+use std::time::Duration;
+
 use pretty_assertions::assert_eq;
 use serde::Deserialize;
 
 use crate::{
     bson::{Bson, Document},
-    client::options::{ClientOptions, ClientOptionsParser, ServerAddress},
+    client::options::{CircuitBreakerOptions, ClientOptions, ClientOptionsParser, ServerAddress},
     error::ErrorKind,
     test::run_spec_test,
 };
@@ -189,3 +191,39 @@ async fn parse_unknown_options() {
     .await;
     parse_uri("maxstalenessms", Some("maxstalenessseconds")).await;
 }
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn parse_max_connecting() {
+    let options = ClientOptions::parse("mongodb://host:27017/?maxConnecting=5")
+        .await
+        .unwrap();
+    assert_eq!(options.max_connecting, Some(5));
+
+    let uri = "mongodb://host:27017/?maxConnecting=0";
+    match ClientOptions::parse(uri).await.map_err(|e| *e.kind) {
+        Err(ErrorKind::InvalidArgument { .. }) => {}
+        other => panic!("expected InvalidArgument for {}, got {:?}", uri, other),
+    }
+
+    ClientOptions::parse("mongodb://host:27017/?maxConnecting=1&waitQueueTimeoutMS=0")
+        .await
+        .expect("waitQueueTimeoutMS should not be constrained by maxConnecting");
+}
+
+#[test]
+fn zero_circuit_breaker_threshold_rejected() {
+    let circuit_breaker = CircuitBreakerOptions::builder()
+        .failure_threshold(0)
+        .window(Duration::from_secs(10))
+        .cool_down(Duration::from_secs(5))
+        .build();
+    let options = ClientOptions::builder()
+        .circuit_breaker(circuit_breaker)
+        .build();
+
+    match options.validate().map_err(|e| *e.kind) {
+        Err(ErrorKind::InvalidArgument { .. }) => {}
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+}
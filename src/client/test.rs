@@ -0,0 +1,251 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+
+use crate::{
+    bson::{doc, Document},
+    cmap::{Connection, Handshaker},
+    event::{
+        cmap::{CmapEventHandler, ConnectionCheckoutFailedEvent},
+        command::{CommandEventHandler, CommandStartedEvent},
+    },
+    operation::{Insert, Retryability},
+    options::ClientOptions,
+    test::start_fake_server,
+    Client,
+    IndexModel,
+    Namespace,
+};
+
+/// Replies to a command sent to the fake cluster, which has the databases "a" (with the
+/// collections "x" and "y") and "b" (with the collection "z"). Listing the indexes of "a.y" fails.
+fn cluster_reply(command: &Document) -> Document {
+    let db = command.get_str("$db").unwrap_or_default();
+    let cursor = |ns: String, batch: Vec<Document>| {
+        doc! { "ok": 1.0, "cursor": { "id": 0_i64, "ns": ns, "firstBatch": batch } }
+    };
+
+    if command.contains_key("listDatabases") {
+        doc! {
+            "ok": 1.0,
+            "databases": [
+                { "name": "a", "sizeOnDisk": 0.0, "empty": false },
+                { "name": "b", "sizeOnDisk": 0.0, "empty": false },
+            ],
+        }
+    } else if command.contains_key("listCollections") {
+        // Views have no indexes, so only collections should be listed.
+        assert_eq!(
+            command.get_document("filter"),
+            Ok(&doc! { "type": "collection" })
+        );
+        let names: &[&str] = if db == "a" { &["x", "y"] } else { &["z"] };
+        let batch = names
+            .iter()
+            .map(|name| doc! { "name": *name, "type": "collection" })
+            .collect();
+        cursor(format!("{}.$cmd.listCollections", db), batch)
+    } else if let Ok(coll) = command.get_str("listIndexes") {
+        if db == "a" && coll == "y" {
+            return doc! { "ok": 0.0, "code": 26, "errmsg": "ns does not exist" };
+        }
+        let batch = vec![doc! { "v": 2, "key": { "_id": 1 }, "name": "_id_" }];
+        cursor(format!("{}.{}", db, coll), batch)
+    } else {
+        doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 13,
+            "minWireVersion": 0,
+            "connectionId": 42,
+        }
+    }
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn list_all_indexes() {
+    let options = ClientOptions::builder()
+        .hosts(vec![start_fake_server(|command| Some(cluster_reply(command)))])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let results: Vec<_> = client.list_all_indexes(2).collect().await;
+    assert_eq!(results.len(), 3);
+
+    let mut indexes: Vec<String> = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .map(|(ns, index)| {
+            let name = index.options.as_ref().and_then(|o| o.name.clone());
+            format!("{}:{}", ns, name.unwrap())
+        })
+        .collect();
+    indexes.sort();
+    assert_eq!(indexes, vec!["a.x:_id_", "b.z:_id_"]);
+
+    let errors: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), Some(26));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn command_started_event_carries_server_connection_id() {
+    #[derive(Default)]
+    struct StartedEvents(Mutex<Vec<CommandStartedEvent>>);
+
+    impl CommandEventHandler for StartedEvents {
+        fn handle_command_started_event(&self, event: CommandStartedEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    let handler = Arc::new(StartedEvents::default());
+    let options = ClientOptions::builder()
+        .hosts(vec![start_fake_server(|command| Some(cluster_reply(command)))])
+        .server_selection_timeout(Duration::from_secs(5))
+        .command_event_handler(Some(handler.clone() as Arc<dyn CommandEventHandler>))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let indexes: Vec<_> = client
+        .database("a")
+        .collection::<Document>("x")
+        .list_indexes(None)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(indexes.len(), 1);
+
+    let events = handler.0.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event.command_name == "listIndexes")
+        .expect("listIndexes should have been started");
+    assert_eq!(event.connection.server_id, Some(42));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn plan_index_changes_lists_current_indexes() {
+    let options = ClientOptions::builder()
+        .hosts(vec![start_fake_server(|command| Some(cluster_reply(command)))])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    // The collection only has its `_id` index, which is kept.
+    let desired = vec![IndexModel::builder().keys(doc! { "a": 1 }).build()];
+    let commands = client
+        .database("a")
+        .collection::<Document>("x")
+        .plan_index_changes(&desired)
+        .await
+        .unwrap();
+    assert_eq!(
+        commands,
+        vec![doc! {
+            "createIndexes": "x",
+            "indexes": [{ "key": { "a": 1 }, "name": "a_1" }],
+        }]
+    );
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn drained_pool_error_retried() {
+    #[derive(Default)]
+    struct CheckoutFailures(AtomicUsize);
+
+    impl CmapEventHandler for CheckoutFailures {
+        fn handle_connection_checkout_failed_event(&self, _event: ConnectionCheckoutFailedEvent) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let address = start_fake_server(|command| Some(cluster_reply(command)));
+    let handler = Arc::new(CheckoutFailures::default());
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(5))
+        .cmap_event_handler(Some(handler.clone() as Arc<dyn CmapEventHandler>))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    // Drain the pool as if the server had been removed from the topology after being selected.
+    let server = client.inner.topology.get_servers().await[&address]
+        .upgrade()
+        .unwrap();
+    server.pool.drain();
+
+    // The executor retries the operation once the checkout fails, and the retry's checkout fails
+    // too since the server is still the only one in the topology.
+    let error = client
+        .list_database_names(None, None)
+        .await
+        .expect_err("the pool is draining");
+    assert!(error.is_pool_cleared(), "{}", error);
+    assert_eq!(handler.0.load(Ordering::SeqCst), 2);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn retryable_writes_enabled_for_wire_version_6_when_unset() {
+    // A replica set member with the oldest wire version that supports retryable writes.
+    let address = start_fake_server(|_| {
+        Some(doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "setName": "rs",
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "logicalSessionTimeoutMinutes": 30,
+            "maxWireVersion": 6,
+            "minWireVersion": 0,
+        })
+    });
+    let mut conn = Connection::new_testing(1, address.clone(), 0, None)
+        .await
+        .unwrap();
+    Handshaker::new(None).handshake(&mut conn).await.unwrap();
+
+    let cases = vec![
+        (None, Retryability::Write),
+        (Some(true), Retryability::Write),
+        (Some(false), Retryability::None),
+    ];
+    for (retry_writes, expected) in cases {
+        let options = ClientOptions::builder()
+            .hosts(vec![address.clone()])
+            .retry_writes(retry_writes)
+            .build();
+        let client = Client::with_options(options).unwrap();
+        let document = doc! { "x": 1 };
+        let insert = Insert::new(
+            Namespace {
+                db: "db".to_string(),
+                coll: "coll".to_string(),
+            },
+            vec![&document],
+            None,
+        );
+        assert_eq!(
+            client.get_retryability(&conn, &insert, &None).await.unwrap(),
+            expected,
+            "retryWrites={:?}",
+            retry_writes
+        );
+    }
+}
@@ -9,7 +9,7 @@ use std::{
 
 use derivative::Derivative;
 
-use self::wire::Message;
+pub(crate) use self::wire::Message;
 use super::manager::PoolManager;
 use crate::{
     bson::oid::ObjectId,
@@ -30,6 +30,8 @@ use crate::{
     options::{ServerAddress, TlsOptions},
     runtime::AsyncStream,
 };
+#[cfg(test)]
+use crate::runtime::CustomStream;
 pub(crate) use command::{Command, RawCommand, RawCommandResponse};
 pub(crate) use stream_description::StreamDescription;
 pub(crate) use wire::next_request_id;
@@ -40,6 +42,11 @@ pub struct ConnectionInfo {
     /// A driver-generated identifier that uniquely identifies the connection.
     pub id: u32,
 
+    /// The server's identifier for the connection, as reported in the connection's handshake.
+    /// This matches the connection id in the server's logs, so it can be used to find a command
+    /// there. Servers that don't report one leave this as `None`.
+    pub server_id: Option<i64>,
+
     /// The address that the connection is connected to.
     pub address: ServerAddress,
 }
@@ -55,6 +62,10 @@ pub(crate) struct Connection {
     /// The cached StreamDescription from the connection's handshake.
     pub(super) stream_description: Option<StreamDescription>,
 
+    /// The server's identifier for this connection, as reported in the last handshake reply that
+    /// was received on it.
+    pub(crate) server_connection_id: Option<i64>,
+
     /// Marks the time when the connection was last checked into the pool. This is used
     /// to detect if the connection is idle.
     ready_and_available_time: Option<Instant>,
@@ -90,22 +101,51 @@ impl Connection {
             address: address.clone(),
             connect_timeout: options.as_ref().and_then(|opts| opts.connect_timeout),
             tls_options: options.as_ref().and_then(|opts| opts.tls_options.clone()),
+            tls_sni_override: options
+                .as_ref()
+                .and_then(|opts| opts.tls_sni_override.clone()),
         };
 
-        let conn = Self {
+        let stream = AsyncStream::connect(stream_options).await?;
+
+        Ok(Self::from_stream(id, address, generation, stream, options))
+    }
+
+    /// Constructs a new connection over an in-memory transport rather than connecting to `address`
+    /// over TCP, so tests can stand in for the server. The connection still needs to be
+    /// handshaked before use.
+    #[cfg(test)]
+    pub(crate) fn with_stream(
+        id: u32,
+        address: ServerAddress,
+        generation: u32,
+        stream: impl CustomStream,
+        options: Option<ConnectionOptions>,
+    ) -> Self {
+        let stream = AsyncStream::Custom(Box::new(stream));
+        Self::from_stream(id, address, generation, stream, options)
+    }
+
+    fn from_stream(
+        id: u32,
+        address: ServerAddress,
+        generation: u32,
+        stream: AsyncStream,
+        options: Option<ConnectionOptions>,
+    ) -> Self {
+        Self {
             id,
             generation: ConnectionGeneration::Normal(generation),
             pool_manager: None,
             command_executing: false,
             ready_and_available_time: None,
-            stream: AsyncStream::connect(stream_options).await?,
+            stream,
             address,
             handler: options.and_then(|options| options.event_handler),
             stream_description: None,
+            server_connection_id: None,
             error: false,
-        };
-
-        Ok(conn)
+        }
     }
 
     /// Constructs and connects a new connection.
@@ -130,6 +170,7 @@ impl Connection {
         address: ServerAddress,
         connect_timeout: Option<Duration>,
         tls_options: Option<TlsOptions>,
+        tls_sni_override: Option<String>,
     ) -> Result<Self> {
         Self::new(
             0,
@@ -138,6 +179,7 @@ impl Connection {
             Some(ConnectionOptions {
                 connect_timeout,
                 tls_options,
+                tls_sni_override,
                 event_handler: None,
             }),
         )
@@ -157,6 +199,7 @@ impl Connection {
     pub(crate) fn info(&self) -> ConnectionInfo {
         ConnectionInfo {
             id: self.id,
+            server_id: self.server_connection_id,
             address: self.address.clone(),
         }
     }
@@ -165,6 +208,16 @@ impl Connection {
         &self.address
     }
 
+    /// The DER encoding of the certificate the server presented, if this connection uses TLS.
+    pub(crate) fn peer_certificate(&self) -> Option<Vec<u8>> {
+        self.stream.peer_certificate()
+    }
+
+    /// The TLS protocol version negotiated for this connection, if it uses TLS.
+    pub(crate) fn tls_protocol_version(&self) -> Option<rustls::ProtocolVersion> {
+        self.stream.tls_protocol_version()
+    }
+
     /// Helper to mark the time that the connection was checked into the pool for the purpose of
     /// detecting when it becomes idle.
     pub(super) fn mark_as_available(&mut self) {
@@ -201,6 +254,12 @@ impl Connection {
         self.error
     }
 
+    /// Marks the connection as unusable, ensuring that it will be closed rather than being checked
+    /// back into the pool.
+    pub(super) fn mark_as_errored(&mut self) {
+        self.error = true;
+    }
+
     /// Helper to create a `ConnectionCheckedOutEvent` for the connection.
     pub(super) fn checked_out_event(&self) -> ConnectionCheckedOutEvent {
         ConnectionCheckedOutEvent {
@@ -309,6 +368,7 @@ impl Connection {
             stream: std::mem::replace(&mut self.stream, AsyncStream::Null),
             handler: self.handler.take(),
             stream_description: self.stream_description.take(),
+            server_connection_id: self.server_connection_id,
             command_executing: self.command_executing,
             error: self.error,
             pool_manager: None,
@@ -11,6 +11,9 @@ pub(crate) struct StreamDescription {
     /// The type of the server when the handshake occurred.
     pub(crate) initial_server_type: ServerType,
 
+    /// Whether the server identified itself as a mongos when the handshake occurred.
+    pub(crate) is_mongos: bool,
+
     /// The maximum wire version that the server understands.
     pub(crate) max_wire_version: Option<i32>,
 
@@ -39,6 +42,7 @@ impl StreamDescription {
         Self {
             server_address: reply.server_address,
             initial_server_type: reply.command_response.server_type(),
+            is_mongos: reply.command_response.is_mongos(),
             max_wire_version: reply.command_response.max_wire_version,
             min_wire_version: reply.command_response.min_wire_version,
             sasl_supported_mechs: reply.command_response.sasl_supported_mechs,
@@ -71,6 +75,7 @@ impl StreamDescription {
         Self {
             server_address: Default::default(),
             initial_server_type: Default::default(),
+            is_mongos: false,
             max_wire_version: Some(max_wire_version),
             min_wire_version: Some(max_wire_version),
             sasl_supported_mechs: Default::default(),
@@ -1,7 +1,7 @@
 use std::io::Read;
 
 use bitflags::bitflags;
-use futures_io::AsyncWrite;
+use futures_io::{AsyncRead, AsyncWrite};
 use futures_util::{
     io::{BufReader, BufWriter},
     AsyncReadExt,
@@ -16,7 +16,7 @@ use crate::{
         Command,
     },
     error::{Error, ErrorKind, Result},
-    runtime::{AsyncLittleEndianWrite, AsyncStream, SyncLittleEndianRead},
+    runtime::{AsyncLittleEndianWrite, SyncLittleEndianRead},
 };
 
 /// Represents an OP_MSG wire protocol operation.
@@ -80,7 +80,7 @@ impl Message {
     }
 
     /// Reads bytes from `reader` and deserializes them into a Message.
-    pub(crate) async fn read_from(reader: &mut AsyncStream) -> Result<Self> {
+    pub(crate) async fn read_from<R: AsyncRead + Unpin + Send>(reader: &mut R) -> Result<Self> {
         let mut reader = BufReader::new(reader);
         let header = Header::read_from(&mut reader).await?;
 
@@ -123,12 +123,15 @@ impl Message {
             flags,
             sections,
             checksum,
-            request_id: None,
+            request_id: Some(header.request_id),
         })
     }
 
     /// Serializes the Message to bytes and writes them to `writer`.
-    pub(crate) async fn write_to(&self, stream: &mut AsyncStream) -> Result<()> {
+    pub(crate) async fn write_to<W>(&self, stream: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
         let mut writer = BufWriter::new(stream);
         let mut sections_bytes = Vec::new();
 
@@ -166,6 +169,61 @@ impl Message {
     }
 }
 
+#[cfg(test)]
+impl Message {
+    /// Reads a single OP_MSG from `reader`, returning its request id and command document. Used
+    /// by tests that stand in for a server.
+    pub(crate) async fn read_command<R: AsyncRead + Unpin + Send>(
+        reader: &mut R,
+    ) -> Result<(i32, bson::Document)> {
+        let message = Self::read_from(reader).await?;
+        let request_id = message.request_id.unwrap_or_default();
+        let command = bson::from_slice(&message.single_document_response()?)?;
+        Ok((request_id, command))
+    }
+
+    /// Writes `reply` to `writer` as an OP_MSG reply to the given request id. Used by tests that
+    /// stand in for a server.
+    pub(crate) async fn write_reply<W: AsyncWrite + Unpin + Send>(
+        writer: &mut W,
+        response_to: i32,
+        reply: &bson::Document,
+    ) -> Result<()> {
+        let mut message = Self::with_raw_command(
+            RawCommand {
+                bytes: bson::to_vec(reply)?,
+                target_db: String::new(),
+                name: String::new(),
+            },
+            None,
+        );
+        message.response_to = response_to;
+        message.write_to(writer).await
+    }
+
+    /// Blocking version of [`Message::read_command`] for servers running on their own thread.
+    pub(crate) fn read_command_blocking(
+        reader: &mut (impl std::io::Read + Send),
+    ) -> Result<(i32, bson::Document)> {
+        futures_executor::block_on(Self::read_command(&mut futures_util::io::AllowStdIo::new(
+            reader,
+        )))
+    }
+
+    /// Blocking version of [`Message::write_reply`] for servers running on their own thread.
+    pub(crate) fn write_reply_blocking(
+        writer: &mut (impl std::io::Write + Send),
+        response_to: i32,
+        reply: &bson::Document,
+    ) -> Result<()> {
+        futures_executor::block_on(Self::write_reply(
+            &mut futures_util::io::AllowStdIo::new(writer),
+            response_to,
+            reply,
+        ))
+    }
+}
+
 bitflags! {
     /// Represents the bitwise flags for an OP_MSG as defined in the spec.
     pub(crate) struct MessageFlags: u32 {
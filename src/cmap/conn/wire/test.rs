@@ -32,6 +32,7 @@ async fn basic() {
         address: CLIENT_OPTIONS.hosts[0].clone(),
         connect_timeout: None,
         tls_options: None,
+        tls_sni_override: None,
     };
 
     let mut stream = AsyncStream::connect(options).await.unwrap();
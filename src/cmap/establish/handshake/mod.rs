@@ -1,17 +1,46 @@
 #[cfg(test)]
 mod test;
 
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use derivative::Derivative;
 use lazy_static::lazy_static;
 use os_info::{Type, Version};
+use rustls::ProtocolVersion;
+use tokio::sync::Mutex;
 
 use crate::{
-    bson::{doc, Bson, Document},
+    bson::{self, doc, Bson, Document},
     client::auth::{ClientFirst, FirstRound},
     cmap::{options::ConnectionPoolOptions, Command, Connection, StreamDescription},
-    error::{ErrorKind, Result},
-    is_master::{is_master_command, run_is_master, IsMasterReply},
-    options::{AuthMechanism, ClientOptions, Credential, DriverInfo, ServerApi},
+    error::{Error, ErrorKind, Result},
+    event::sdam::{
+        SdamEventHandler,
+        ServerHeartbeatFailedEvent,
+        ServerHeartbeatStartedEvent,
+        ServerHeartbeatSucceededEvent,
+    },
+    is_master::{is_master_command, run_is_master, IsMasterCommandResponse, IsMasterReply},
+    options::{
+        AuthMechanism,
+        ClientOptions,
+        Credential,
+        DriverInfo,
+        ServerAddress,
+        ServerApi,
+        WriteConcern,
+    },
+    sdam::ServerType,
+    RUNTIME,
 };
+#[cfg(test)]
+use crate::{cmap::RawCommandResponse, is_master::parse_is_master_response};
 
 #[cfg(feature = "tokio-runtime")]
 const RUNTIME_NAME: &str = "tokio";
@@ -22,39 +51,144 @@ const RUNTIME_NAME: &str = "async-std";
 #[cfg(feature = "sync")]
 const RUNTIME_NAME: &str = "sync (with async-std)";
 
+/// The maximum size in bytes of the client metadata sent in the handshake. Servers reject
+/// handshakes with larger metadata.
+const MAX_METADATA_SIZE: usize = 512;
+
+/// The metadata describing the client that is sent in the `client` field of the handshake.
 #[derive(Clone, Debug)]
-struct ClientMetadata {
-    application: Option<AppMetadata>,
-    driver: DriverMetadata,
-    os: OsMetadata,
-    platform: Option<String>,
+#[non_exhaustive]
+pub struct ClientMetadata {
+    /// The application the client belongs to, sent in the `application` field if an `app_name`
+    /// is configured.
+    pub application: Option<AppMetadata>,
+
+    /// The name of the machine the driver is running on, sent in the `x-hostname` field.
+    pub hostname: Option<String>,
+
+    /// The driver, sent in the `driver` field.
+    pub driver: DriverMetadata,
+
+    /// The operating system the driver is running on, sent in the `os` field.
+    pub os: OsMetadata,
+
+    /// The compiler and async runtime the driver was built with, sent in the `platform` field.
+    pub platform: Option<String>,
+
+    /// The environment the driver is running in, sent in the `env` field.
+    pub env: Option<EnvMetadata>,
 }
 
+/// The application the client belongs to, sent in the `application` field of the metadata.
 #[derive(Clone, Debug)]
-struct AppMetadata {
-    name: String,
+#[non_exhaustive]
+pub struct AppMetadata {
+    /// The name of the application.
+    pub name: String,
+
+    /// The version of the framework the application is built on.
+    pub version: Option<String>,
 }
 
+/// The driver, sent in the `driver` field of the metadata.
 #[derive(Clone, Debug)]
-struct DriverMetadata {
-    name: String,
-    version: String,
+#[non_exhaustive]
+pub struct DriverMetadata {
+    /// The name of the driver, including the names of any wrapping libraries.
+    pub name: String,
+
+    /// The version of the driver, including the versions of any wrapping libraries.
+    pub version: String,
 }
 
+/// The operating system the driver is running on, sent in the `os` field of the metadata.
 #[derive(Clone, Debug)]
-struct OsMetadata {
-    os_type: String,
-    name: Option<String>,
-    architecture: String,
-    version: Option<String>,
+#[non_exhaustive]
+pub struct OsMetadata {
+    /// The type of the operating system, e.g. `linux`.
+    pub os_type: String,
+
+    /// The name of the operating system.
+    pub name: Option<String>,
+
+    /// The processor architecture, e.g. `x86_64`.
+    pub architecture: Option<String>,
+
+    /// The version of the operating system.
+    pub version: Option<String>,
+}
+
+/// The environment the driver is running in, sent in the `env` field of the metadata.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct EnvMetadata {
+    /// The container the driver is running in, if any.
+    pub container: Option<ContainerMetadata>,
+}
+
+/// The container the driver is running in, sent in the `env.container` field of the metadata.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ContainerMetadata {
+    /// The container runtime, e.g. `docker`.
+    pub runtime: Option<String>,
+
+    /// The container orchestrator, e.g. `kubernetes`.
+    pub orchestrator: Option<String>,
 }
 
+impl ContainerMetadata {
+    /// Describes the container the driver is running in, given whether a `/.dockerenv` file exists
+    /// and whether the `KUBERNETES_SERVICE_HOST` environment variable is set, or returns `None` if
+    /// neither indicates a container.
+    fn detect(dockerenv_exists: bool, kubernetes_service_host_set: bool) -> Option<Self> {
+        if !dockerenv_exists && !kubernetes_service_host_set {
+            return None;
+        }
+        Some(Self {
+            runtime: if dockerenv_exists {
+                Some("docker".to_string())
+            } else {
+                None
+            },
+            orchestrator: if kubernetes_service_host_set {
+                Some("kubernetes".to_string())
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Encodes the client metadata into the value sent in the `client` field of the handshake. The
+/// default implementation sends every field of the metadata; implementations can override it to
+/// control exactly what is sent, e.g. to drop optional fields in size-constrained environments.
+pub trait MetadataEncoder: Send + Sync {
+    /// Encodes the given metadata.
+    fn encode(&self, metadata: &ClientMetadata) -> Bson {
+        metadata.clone().into()
+    }
+}
+
+/// The encoder used when no other one is configured.
+struct DefaultMetadataEncoder;
+
+impl MetadataEncoder for DefaultMetadataEncoder {}
+
 impl From<ClientMetadata> for Bson {
     fn from(metadata: ClientMetadata) -> Self {
         let mut metadata_doc = Document::new();
 
         if let Some(application) = metadata.application {
-            metadata_doc.insert("application", doc! { "name": application.name });
+            let mut application_doc = doc! { "name": application.name };
+            if let Some(version) = application.version {
+                application_doc.insert("version", version);
+            }
+            metadata_doc.insert("application", application_doc);
+        }
+
+        if let Some(hostname) = metadata.hostname {
+            metadata_doc.insert("x-hostname", hostname);
         }
 
         metadata_doc.insert(
@@ -71,10 +205,89 @@ impl From<ClientMetadata> for Bson {
             metadata_doc.insert("platform", platform);
         }
 
+        if let Some(env) = metadata.env {
+            let mut env_doc = Document::new();
+            if let Some(container) = env.container {
+                let mut container_doc = Document::new();
+                if let Some(runtime) = container.runtime {
+                    container_doc.insert("runtime", runtime);
+                }
+                if let Some(orchestrator) = container.orchestrator {
+                    container_doc.insert("orchestrator", orchestrator);
+                }
+                env_doc.insert("container", container_doc);
+            }
+            metadata_doc.insert("env", env_doc);
+        }
+
         Bson::Document(metadata_doc)
     }
 }
 
+impl ClientMetadata {
+    /// The size of this metadata in bytes once encoded as BSON.
+    fn encoded_size(&self) -> usize {
+        match Bson::from(self.clone()) {
+            Bson::Document(doc) => bson::to_vec(&doc).map(|bytes| bytes.len()).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Drops optional fields until the metadata fits in `MAX_METADATA_SIZE` bytes: first the
+    /// hostname, then the environment, then the application's version, then the fields of the OS
+    /// metadata other than its type, and then the platform.
+    fn truncate(&mut self) {
+        if self.encoded_size() <= MAX_METADATA_SIZE {
+            return;
+        }
+        self.hostname = None;
+
+        if self.encoded_size() <= MAX_METADATA_SIZE {
+            return;
+        }
+        self.env = None;
+
+        if self.encoded_size() <= MAX_METADATA_SIZE {
+            return;
+        }
+        if let Some(ref mut application) = self.application {
+            application.version = None;
+        }
+
+        if self.encoded_size() <= MAX_METADATA_SIZE {
+            return;
+        }
+        self.os.name = None;
+        self.os.architecture = None;
+        self.os.version = None;
+
+        if self.encoded_size() <= MAX_METADATA_SIZE {
+            return;
+        }
+        self.platform = None;
+    }
+}
+
+/// Prepares the application name for the handshake metadata by removing any control characters
+/// and then applying the configured sanitizer, if any.
+fn sanitize_app_name(app_name: String, sanitizer: Option<&AppNameSanitizer>) -> String {
+    let app_name: String = app_name.chars().filter(|c| !c.is_control()).collect();
+    match sanitizer {
+        Some(sanitizer) => sanitizer(app_name),
+        None => app_name,
+    }
+}
+
+/// The name of the machine the driver is running on, if it can be determined.
+fn local_hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+}
+
 impl From<OsMetadata> for Bson {
     fn from(metadata: OsMetadata) -> Self {
         let mut doc = doc! { "type": metadata.os_type };
@@ -83,7 +296,9 @@ impl From<OsMetadata> for Bson {
             doc.insert("name", name);
         }
 
-        doc.insert("architecture", metadata.architecture);
+        if let Some(architecture) = metadata.architecture {
+            doc.insert("architecture", architecture);
+        }
 
         if let Some(version) = metadata.version {
             doc.insert("version", version);
@@ -100,17 +315,19 @@ lazy_static! {
     static ref BASE_CLIENT_METADATA: ClientMetadata = {
         let mut metadata = ClientMetadata {
             application: None,
+            hostname: None,
             driver: DriverMetadata {
                 name: "mongo-rust-driver".into(),
-                version: env!("CARGO_PKG_VERSION").into(),
+                version: String::new(),
             },
             os: OsMetadata {
                 os_type: std::env::consts::OS.into(),
-                architecture: std::env::consts::ARCH.into(),
+                architecture: Some(std::env::consts::ARCH.into()),
                 name: None,
                 version: None,
             },
             platform: None,
+            env: None,
         };
 
         let info = os_info::get();
@@ -130,29 +347,172 @@ lazy_static! {
 
         metadata
     };
+
+    /// The environment the driver is running in. Probing it touches the filesystem and the process
+    /// environment, so it is only done once, when the first handshaker is created.
+    static ref ENV_METADATA: Option<EnvMetadata> = detect_env();
+}
+
+/// The number of times the environment has been probed.
+#[cfg(test)]
+static ENV_PROBES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Probes the environment the driver is running in, returning `None` if nothing about it is
+/// reported. Use the cached `ENV_METADATA` instead of calling this directly.
+fn detect_env() -> Option<EnvMetadata> {
+    #[cfg(test)]
+    ENV_PROBES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+    ContainerMetadata::detect(
+        std::path::Path::new("/.dockerenv").exists(),
+        std::env::var_os("KUBERNETES_SERVICE_HOST").is_some(),
+    )
+    .map(|container| EnvMetadata {
+        container: Some(container),
+    })
+}
+
+/// A version of the MongoDB wire protocol, as reported in the `maxWireVersion` field of the
+/// handshake reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct WireVersion(pub(crate) i32);
+
+impl WireVersion {
+    /// The earliest server release that supports this wire version, if known.
+    fn server_version(self) -> Option<&'static str> {
+        let version = match self.0 {
+            0 => "2.4",
+            1 | 2 => "2.6",
+            3 => "3.0",
+            4 => "3.2",
+            5 => "3.4",
+            6 => "3.6",
+            7 => "4.0",
+            8 => "4.2",
+            9 => "4.4",
+            13 => "5.0",
+            _ => return None,
+        };
+        Some(version)
+    }
+}
+
+impl std::fmt::Display for WireVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.server_version() {
+            Some(version) => write!(f, "wire version {} (MongoDB {})", self.0, version),
+            None => write!(f, "wire version {}", self.0),
+        }
+    }
 }
 
 /// Contains the logic needed to handshake a connection.
-#[derive(Debug, Clone)]
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
 pub(crate) struct Handshaker {
     /// The `isMaster` command to send when handshaking. This will always be identical
     /// given the same pool options, so it can be created at the time the Handshaker is created.
     command: Command,
     credential: Option<Credential>,
+
+    /// The database the handshake is sent to in place of the credential's auth source, if any.
+    target_db_override: Option<String>,
+
+    /// The default write concern to check against the server's wire version in strict mode.
+    write_concern: Option<WriteConcern>,
+
+    /// Whether a write concern the server cannot honor should fail the handshake.
+    strict_write_concern: bool,
+
+    /// The request id to use for the wire message carrying the `isMaster` command, if any.
+    request_id_hint: Option<i32>,
+
+    /// The minimum wire version a server must support to be connected to.
+    min_server_version: Option<WireVersion>,
+
+    /// Recent replies that can be used in place of running `isMaster`, if any.
+    reply_cache: Option<HandshakeReplyCache>,
+
+    /// Invoked with a summary of each successful handshake.
+    #[derivative(Debug = "ignore")]
+    on_handshake_complete: Option<HandshakeCallback>,
+
+    /// Whether authentication is restricted to FIPS-approved mechanisms.
+    fips_mode: bool,
+
+    /// The maximum time to wait for the `isMaster` exchange, if any.
+    is_master_timeout: Option<Duration>,
+
+    /// The maximum time to wait for authentication once the `isMaster` exchange completes, if any.
+    auth_timeout: Option<Duration>,
+
+    /// The handler to notify of the server check that the `isMaster` exchange amounts to, if any.
+    #[derivative(Debug = "ignore")]
+    sdam_event_handler: Option<Arc<dyn SdamEventHandler>>,
+
+    /// Verifies the certificate presented by a replica set member against its set name, if any.
+    #[derivative(Debug = "ignore")]
+    cert_matches_set: Option<CertMatchesSet>,
+
+    /// The oldest TLS protocol version a connection may have negotiated, if restricted.
+    min_tls_version: Option<ProtocolVersion>,
+
+    /// The serialized size in bytes of the `client` metadata document sent in the handshake.
+    metadata_size: usize,
+
+    /// The mechanisms speculative authentication is attempted for, if restricted.
+    speculative_auth_mechanisms: Option<HashSet<AuthMechanism>>,
+
+    /// Why the handshake metadata failed driver name validation, if it was enabled and failed.
+    /// Every handshake fails with this message rather than connecting with the bad metadata.
+    invalid_driver_name: Option<String>,
 }
 
 impl Handshaker {
     /// Creates a new Handshaker.
     pub(crate) fn new(options: Option<HandshakerOptions>) -> Self {
         let mut metadata = BASE_CLIENT_METADATA.clone();
+        metadata.env = ENV_METADATA.clone();
         let mut credential = None;
+        let mut target_db_override = None;
+        let mut write_concern = None;
+        let mut strict_write_concern = false;
+        let mut request_id_hint = None;
+        let mut min_server_version = None;
+        let mut reply_cache = None;
+        let mut on_handshake_complete = None;
+        let mut full_metadata_override = None;
+        let mut fips_mode = false;
+        let mut is_master_timeout = None;
+        let mut auth_timeout = None;
+        let mut sdam_event_handler = None;
+        let mut cert_matches_set = None;
+        let mut min_tls_version = None;
+        let mut validate_driver_name = false;
+        let mut include_hostname = false;
+        let mut omit_platform = false;
+        let mut metadata_encoder = None;
+        let mut speculative_auth_mechanisms = None;
+        let mut extra_command_fields = None;
 
         let mut command =
             is_master_command(options.as_ref().and_then(|opts| opts.server_api.as_ref()));
 
+        // Tell the server that `hello` replies are accepted, so it can keep using them after the
+        // handshake even if the handshake itself uses the legacy `isMaster` command.
+        command.body.insert("helloOk", true);
+
+        metadata.driver.version = options
+            .as_ref()
+            .and_then(|opts| opts.driver_version.clone())
+            .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+
         if let Some(options) = options {
             if let Some(app_name) = options.app_name {
-                metadata.application = Some(AppMetadata { name: app_name });
+                metadata.application = Some(AppMetadata {
+                    name: sanitize_app_name(app_name, options.app_name_sanitizer.as_ref()),
+                    version: options.framework_version,
+                });
             }
 
             if let Some(driver_info) = options.driver_info {
@@ -178,26 +538,306 @@ impl Handshaker {
                 credential = Some(cred);
             }
 
+            target_db_override = options.target_db_override;
+            if let Some(ref target_db) = target_db_override {
+                command.target_db = target_db.clone();
+            }
+
             if options.load_balanced {
                 command.body.insert("loadBalanced", true);
             }
+
+            write_concern = options.write_concern;
+            strict_write_concern = options.strict_write_concern;
+            request_id_hint = options.request_id_hint;
+            min_server_version = options.min_server_version;
+            reply_cache = options.reply_cache;
+            on_handshake_complete = options.on_handshake_complete;
+            full_metadata_override = options.full_metadata_override;
+            fips_mode = options.fips_mode;
+            is_master_timeout = options.is_master_timeout;
+            auth_timeout = options.auth_timeout;
+            sdam_event_handler = options.sdam_event_handler;
+            cert_matches_set = options.cert_matches_set;
+            min_tls_version = options.min_tls_version;
+            validate_driver_name = options.validate_driver_name;
+            include_hostname = options.include_hostname;
+            omit_platform = options.omit_platform;
+            metadata_encoder = options.metadata_encoder;
+            speculative_auth_mechanisms = options.speculative_auth_mechanisms;
+            extra_command_fields = options.extra_command_fields;
+        }
+
+        if include_hostname {
+            metadata.hostname = local_hostname();
+        }
+
+        if omit_platform {
+            metadata.platform = None;
         }
 
+        // Only metadata assembled and encoded by the driver itself is checked below.
+        let driver_built_metadata = full_metadata_override.is_none() && metadata_encoder.is_none();
+        let metadata: Bson = match full_metadata_override {
+            Some(metadata) => metadata.into(),
+            None => {
+                metadata.truncate();
+                let encoder = metadata_encoder
+                    .as_deref()
+                    .unwrap_or(&DefaultMetadataEncoder as &dyn MetadataEncoder);
+                encoder.encode(&metadata)
+            }
+        };
+        let metadata_size = match metadata {
+            Bson::Document(ref doc) => bson::to_vec(doc).map(|bytes| bytes.len()).unwrap_or(0),
+            _ => 0,
+        };
         command.body.insert("client", metadata);
 
+        let invalid_driver_name = if validate_driver_name {
+            check_driver_name(&command).err()
+        } else {
+            None
+        };
+
+        // This only checks the command as assembled by the driver itself, which a failure
+        // indicates a bug in. User-supplied metadata may deliberately omit fields the check
+        // requires.
+        if driver_built_metadata {
+            debug_assert_eq!(
+                validate_handshake_command(&command),
+                Ok(()),
+                "malformed handshake command {:?}",
+                command.body
+            );
+        }
+
+        // Added after the check above, since the extra fields are deliberately not ones the
+        // handshake spec allows.
+        if let Some(fields) = extra_command_fields {
+            for (name, value) in fields {
+                if !RESERVED_HANDSHAKE_FIELDS.contains(&name.as_str()) && !name.starts_with('$') {
+                    command.body.insert(name, value);
+                }
+            }
+        }
+
         Self {
             command,
             credential,
+            target_db_override,
+            write_concern,
+            strict_write_concern,
+            request_id_hint,
+            min_server_version,
+            reply_cache,
+            on_handshake_complete,
+            fips_mode,
+            is_master_timeout,
+            auth_timeout,
+            sdam_event_handler,
+            cert_matches_set,
+            min_tls_version,
+            metadata_size,
+            speculative_auth_mechanisms,
+            invalid_driver_name,
         }
     }
 
+    /// Creates a Handshaker identical to this one except that it authenticates with `credential`.
+    ///
+    /// This reuses the already assembled handshake command, only redoing the parts of it that
+    /// depend on the credential, so it is cheaper than creating a new Handshaker for pools that
+    /// differ only in their credentials. Speculative authentication is based on the credential at
+    /// the time of each handshake, so it needs no updating here.
+    #[allow(dead_code)]
+    pub(crate) fn with_credential(&self, credential: Option<Credential>) -> Handshaker {
+        let mut handshaker = self.clone();
+        let command = &mut handshaker.command;
+
+        command.body.remove("saslSupportedMechs");
+        command.target_db = "admin".to_string();
+        if let Some(ref cred) = credential {
+            cred.append_needed_mechanism_negotiation(&mut command.body);
+            command.target_db = cred.resolved_source().to_string();
+        }
+        if let Some(ref target_db) = self.target_db_override {
+            command.target_db = target_db.clone();
+        }
+
+        handshaker.credential = credential;
+        handshaker
+    }
+
+    /// The `db.user` string sent in the `saslSupportedMechs` field of the handshake to negotiate
+    /// an authentication mechanism, if one is needed.
+    #[cfg(test)]
+    pub(crate) fn mechanism_negotiation_target(&self) -> Option<&str> {
+        self.command.body.get_str("saslSupportedMechs").ok()
+    }
+
     /// Handshakes a connection.
+    ///
+    /// If the returned future is dropped before it completes, the connection may have been left in
+    /// the middle of an exchange with the server, so it will be marked as errored to prevent it
+    /// from being used again.
     pub(crate) async fn handshake(&self, conn: &mut Connection) -> Result<HandshakeResult> {
+        if let Some(ref message) = self.invalid_driver_name {
+            return Err(ErrorKind::InvalidArgument {
+                message: message.clone(),
+            }
+            .into());
+        }
+
+        check_tls_version(self.min_tls_version, conn.tls_protocol_version())?;
+
+        // The handshake of a monitoring connection is the first check of its server.
+        let server_address = conn.address().clone();
+        self.emit_heartbeat_event(|handler| {
+            handler.handle_server_heartbeat_started_event(ServerHeartbeatStartedEvent {
+                server_address: server_address.clone(),
+                awaited: false,
+            })
+        });
+        let start_time = Instant::now();
+        let mut guard = IncompleteHandshakeGuard { conn, armed: true };
+        let completed = match self.is_master_timeout {
+            Some(timeout) => RUNTIME
+                .timeout(timeout, self.handshake_inner(guard.conn))
+                .await
+                .map_err(|_| phase_timeout_error("isMaster", timeout)),
+            None => Ok(self.handshake_inner(guard.conn).await),
+        };
+        // A handshake cut short by the timeout may have left a partial exchange on the connection,
+        // so the guard is only disarmed once the handshake has run to completion.
+        guard.armed = completed.is_err();
+        let result = completed.and_then(|result| result).and_then(|result| {
+            check_cert_matches_set(
+                self.cert_matches_set.as_ref(),
+                &result.is_master_reply.command_response,
+                guard.conn.peer_certificate().as_deref(),
+            )?;
+            Ok(result)
+        });
+
+        let duration = start_time.elapsed();
+        if let (Some(callback), Ok(result)) = (self.on_handshake_complete.as_ref(), result.as_ref())
+        {
+            let auth_mechanism = self.credential.as_ref().map(|credential| {
+                credential.mechanism.clone().unwrap_or_else(|| {
+                    guard
+                        .conn
+                        .stream_description
+                        .as_ref()
+                        .map(AuthMechanism::from_stream_description)
+                        .unwrap_or(AuthMechanism::ScramSha1)
+                })
+            });
+            callback(HandshakeSummary {
+                address: guard.conn.address().clone(),
+                duration,
+                server_type: result.is_master_reply.command_response.server_type(),
+                max_wire_version: result.is_master_reply.command_response.max_wire_version,
+                auth_mechanism,
+                speculative_auth_succeeded: result.first_round.is_some(),
+                metadata_size: self.metadata_size,
+            });
+        }
+
+        match result {
+            Ok(ref result) => self.emit_heartbeat_event(|handler| {
+                handler.handle_server_heartbeat_succeeded_event(ServerHeartbeatSucceededEvent {
+                    duration,
+                    reply: result.is_master_reply.raw_command_response.clone(),
+                    server_address,
+                    awaited: false,
+                })
+            }),
+            Err(ref error) => self.emit_heartbeat_event(|handler| {
+                handler.handle_server_heartbeat_failed_event(ServerHeartbeatFailedEvent {
+                    duration,
+                    failure: error.clone(),
+                    server_address,
+                    awaited: false,
+                })
+            }),
+        }
+
+        result
+    }
+
+    fn emit_heartbeat_event<F>(&self, emit: F)
+    where
+        F: FnOnce(&Arc<dyn SdamEventHandler>),
+    {
+        if let Some(ref handler) = self.sdam_event_handler {
+            emit(handler);
+        }
+    }
+
+    /// Runs the authentication that follows the handshake, failing if it does not complete within
+    /// the configured `auth_timeout`.
+    pub(crate) async fn authenticate_with_timeout<F>(&self, authenticate: F) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        match self.auth_timeout {
+            Some(timeout) => RUNTIME
+                .timeout(timeout, authenticate)
+                .await
+                .unwrap_or_else(|_| Err(phase_timeout_error("authentication", timeout))),
+            None => authenticate.await,
+        }
+    }
+
+    /// Returns the command a monitor sends to check the server once its connection has been
+    /// handshaked. The client metadata and the fields negotiating authentication and compression
+    /// only need to be sent in the handshake, so they are left out to keep heartbeats small. If
+    /// the handshake reply had `helloOk` set, the check uses `hello` rather than the legacy
+    /// `isMaster`.
+    pub(crate) fn monitoring_command(&self, hello_ok: bool) -> Command {
+        let mut command = self.command.clone();
+        for field in &["client", "compression", "saslSupportedMechs"] {
+            command.body.remove(field);
+        }
+        command.target_db = "admin".to_string();
+        if hello_ok && command.name != "hello" {
+            command = hello_command(command);
+        }
+        command
+    }
+
+    async fn handshake_inner(&self, conn: &mut Connection) -> Result<HandshakeResult> {
+        // A fresh reply from another connection to the same server can stand in for this one's.
+        // Speculative authentication is skipped in that case, so any authentication will be done
+        // in full after the handshake.
+        if let Some(ref cache) = self.reply_cache {
+            if let Some(is_master_reply) = cache.get(conn.address()).await {
+                conn.stream_description =
+                    Some(StreamDescription::from_is_master(is_master_reply.clone()));
+                return self.handshake_result(is_master_reply, None);
+            }
+        }
+
         let mut command = self.command.clone();
 
-        let client_first = set_speculative_auth_info(&mut command.body, self.credential.as_ref())?;
+        let client_first = set_speculative_auth_info(
+            &mut command.body,
+            self.credential.as_ref(),
+            self.fips_mode,
+            self.speculative_auth_mechanisms.as_ref(),
+        )?;
 
-        let mut is_master_reply = run_is_master(command, conn).await?;
+        let mut is_master_reply = match run_is_master(command.clone(), conn, self.request_id_hint)
+            .await
+        {
+            // Servers that predate `hello` reject it outright, so fall back to the legacy command
+            // name once.
+            Err(e) if e.is_command_not_found() && command.name == "hello" => {
+                run_is_master(legacy_is_master_command(command), conn, None).await?
+            }
+            result => result?,
+        };
         if self.command.body.contains_key("loadBalanced")
             && is_master_reply.command_response.service_id.is_none()
         {
@@ -209,6 +849,7 @@ impl Handshaker {
             .into());
         }
         conn.stream_description = Some(StreamDescription::from_is_master(is_master_reply.clone()));
+        conn.server_connection_id = is_master_reply.command_response.connection_id;
 
         // Record the client's message and the server's response from speculative authentication if
         // the server did send a response.
@@ -220,11 +861,139 @@ impl Handshaker {
                 .map(|server_first| client_first.into_first_round(server_first))
         });
 
+        let result = self.handshake_result(is_master_reply, first_round)?;
+        if let Some(ref cache) = self.reply_cache {
+            cache
+                .insert(conn.address().clone(), result.is_master_reply.clone())
+                .await;
+        }
+        Ok(result)
+    }
+
+    /// Builds the result of a handshake from the server's reply, checking the configured minimum
+    /// server version and write concern against the server's wire version.
+    fn handshake_result(
+        &self,
+        is_master_reply: IsMasterReply,
+        first_round: Option<FirstRound>,
+    ) -> Result<HandshakeResult> {
+        self.check_min_server_version(&is_master_reply.command_response)?;
+        self.check_supported_mechanisms(&is_master_reply.command_response)?;
+        self.check_write_concern(&is_master_reply.command_response)?;
+
         Ok(HandshakeResult {
+            hello_ok: is_master_reply.command_response.hello_ok == Some(true),
             is_master_reply,
             first_round,
         })
     }
+
+    /// Fails the handshake if mechanism negotiation was requested and the server advertised no
+    /// mechanisms, which means the user exists but has no password-based (SCRAM) credentials.
+    /// Otherwise, authentication would go on to attempt SCRAM and fail with a less helpful error.
+    ///
+    /// In FIPS mode, this also fails the handshake if negotiation would select SCRAM-SHA-1.
+    fn check_supported_mechanisms(&self, response: &IsMasterCommandResponse) -> Result<()> {
+        let user = match self.command.body.get_str("saslSupportedMechs") {
+            Ok(user) => user,
+            Err(_) => return Ok(()),
+        };
+
+        match response.sasl_supported_mechs {
+            Some(ref mechanisms) if mechanisms.is_empty() => Err(Error::authentication_error(
+                "mechanism negotiation",
+                &format!(
+                    "the server reported no SASL mechanisms for user {}, so it has no \
+                     password-based credentials; specify a mechanism such as MONGODB-X509 instead",
+                    user
+                ),
+            )),
+            // Negotiation falls back to SCRAM-SHA-1 unless the server advertises SCRAM-SHA-256.
+            ref mechanisms
+                if self.fips_mode
+                    && !mechanisms
+                        .iter()
+                        .flatten()
+                        .any(|m| m == AuthMechanism::ScramSha256.as_str()) =>
+            {
+                Err(Error::authentication_error(
+                    "mechanism negotiation",
+                    &format!(
+                        "SCRAM-SHA-1 is not permitted in FIPS mode, but it is the only SCRAM \
+                         mechanism available for user {}; use SCRAM-SHA-256 or MONGODB-X509 \
+                         instead",
+                        user
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects servers whose maximum wire version is below the configured minimum.
+    fn check_min_server_version(&self, response: &IsMasterCommandResponse) -> Result<()> {
+        let min_server_version = match self.min_server_version {
+            Some(min_server_version) => min_server_version,
+            None => return Ok(()),
+        };
+
+        let server_version = WireVersion(response.max_wire_version.unwrap_or(0));
+        if server_version < min_server_version {
+            return Err(ErrorKind::IncompatibleServer {
+                message: format!(
+                    "the server only supports up to {}, but the minimum server version policy \
+                     requires at least {}",
+                    server_version, min_server_version
+                ),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the server is new enough to honor the configured write concern in strict
+    /// mode, returning an error if it is not.
+    fn check_write_concern(&self, response: &IsMasterCommandResponse) -> Result<()> {
+        let write_concern = match self.write_concern {
+            Some(ref write_concern) if self.strict_write_concern => write_concern,
+            _ => return Ok(()),
+        };
+
+        let min_wire_version = match write_concern.min_wire_version() {
+            Some(min_wire_version) => min_wire_version,
+            None => return Ok(()),
+        };
+
+        let max_wire_version = response.max_wire_version.unwrap_or(0);
+        if max_wire_version >= min_wire_version {
+            return Ok(());
+        }
+
+        Err(ErrorKind::IncompatibleServer {
+            message: format!(
+                "the configured write concern {:?} requires a server wire version of at least {}, \
+                 but the server only supports up to {}",
+                write_concern, min_wire_version, max_wire_version
+            ),
+        }
+        .into())
+    }
+}
+
+/// Marks a connection as errored when dropped unless it has been disarmed, which happens once the
+/// handshake it guards runs to completion.
+struct IncompleteHandshakeGuard<'a> {
+    conn: &'a mut Connection,
+    armed: bool,
+}
+
+impl Drop for IncompleteHandshakeGuard<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.conn.mark_as_errored();
+        }
+    }
 }
 
 /// The information returned from the server as part of the handshake.
@@ -238,15 +1007,301 @@ pub(crate) struct HandshakeResult {
 
     /// The first round of speculative authentication, if applicable.
     pub(crate) first_round: Option<FirstRound>,
+
+    /// Whether the server echoed the `helloOk` flag sent in the handshake, meaning it supports the
+    /// `hello` command, which can then be used for monitoring instead of the legacy `isMaster`.
+    pub(crate) hello_ok: bool,
 }
 
-#[derive(Debug)]
+impl HandshakeResult {
+    /// Builds a `HandshakeResult` from a captured `isMaster` reply document, performing the same
+    /// parsing as `Handshaker::handshake` but without any I/O or authentication. This allows
+    /// replies captured from real deployments to be replayed offline.
+    #[cfg(test)]
+    pub(crate) fn from_reply_document(doc: Document) -> Result<Self> {
+        let response =
+            RawCommandResponse::with_document_and_address(ServerAddress::default(), doc)?;
+
+        let is_master_reply = parse_is_master_response(response, None)?;
+
+        Ok(Self {
+            hello_ok: is_master_reply.command_response.hello_ok == Some(true),
+            is_master_reply,
+            first_round: None,
+        })
+    }
+}
+
+/// A summary of a completed handshake, passed to the
+/// [`on_handshake_complete`](struct.ClientOptions.html#structfield.on_handshake_complete) callback.
+///
+/// Its `Display` implementation renders the summary on a single line, for including in log lines
+/// and error messages about the connection.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HandshakeSummary {
+    /// The address of the server the handshake was performed with.
+    pub address: ServerAddress,
+
+    /// How long the handshake took, including any retry with the legacy `isMaster` command.
+    pub duration: Duration,
+
+    /// The type of the server, as determined from its handshake reply.
+    pub server_type: ServerType,
+
+    /// The maximum wire version the server reported supporting.
+    pub max_wire_version: Option<i32>,
+
+    /// The mechanism the connection authenticates with, or `None` if no credential is configured.
+    /// If the credential doesn't specify a mechanism, this is the one negotiated from the
+    /// mechanisms the server reported supporting for the user.
+    pub auth_mechanism: Option<AuthMechanism>,
+
+    /// Whether the server accepted the speculative authentication sent with the handshake.
+    pub speculative_auth_succeeded: bool,
+
+    /// The serialized size in bytes of the `client` metadata document sent in the handshake, for
+    /// tracking how close it comes to `MAX_METADATA_SIZE`.
+    pub metadata_size: usize,
+}
+
+impl fmt::Display for HandshakeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "handshake with {}: type {:?}", self.address, self.server_type)?;
+        match self.max_wire_version {
+            Some(version) => write!(f, ", wire version {}", version)?,
+            None => write!(f, ", wire version unknown")?,
+        }
+        match self.auth_mechanism {
+            Some(ref mechanism) => write!(f, ", auth {}", mechanism.as_str())?,
+            None => write!(f, ", no auth")?,
+        }
+        write!(f, ", rtt {:?}", self.duration)
+    }
+}
+
+/// A callback invoked with a summary of each completed handshake.
+pub type HandshakeCallback = Arc<dyn Fn(HandshakeSummary) + Send + Sync>;
+
+/// A check of the certificate presented by a replica set member, invoked with the set name the
+/// member reported in its handshake reply and the DER encoding of its certificate. It should
+/// return whether the certificate is one that member of that set is expected to present.
+pub type CertMatchesSet = Arc<dyn Fn(&str, &[u8]) -> bool + Send + Sync>;
+
+/// A transformation applied to the application name before it is sent in the handshake metadata,
+/// e.g. to redact parts of it. It receives the name with control characters already removed.
+pub type AppNameSanitizer = Arc<dyn Fn(String) -> String + Send + Sync>;
+
+/// Fails the handshake with an authentication error if a certificate check is configured and the
+/// certificate presented by the server does not match the replica set it reported. Servers that
+/// are not replica set members or that did not present a certificate are not checked.
+fn check_cert_matches_set(
+    cert_matches_set: Option<&CertMatchesSet>,
+    response: &IsMasterCommandResponse,
+    peer_certificate: Option<&[u8]>,
+) -> Result<()> {
+    let (cert_matches_set, set_name, peer_certificate) =
+        match (cert_matches_set, response.set_name.as_ref(), peer_certificate) {
+            (Some(check), Some(set_name), Some(cert)) => (check, set_name, cert),
+            _ => return Ok(()),
+        };
+
+    if !cert_matches_set(set_name, peer_certificate) {
+        return Err(Error::authentication_error(
+            "TLS",
+            &format!(
+                "the certificate presented by the server does not match the replica set {}",
+                set_name
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Fails with an authentication error if a minimum TLS version is configured and the connection
+/// negotiated an older one.
+fn check_tls_version(
+    min_tls_version: Option<ProtocolVersion>,
+    negotiated: Option<ProtocolVersion>,
+) -> Result<()> {
+    match (min_tls_version, negotiated) {
+        (Some(min), Some(negotiated)) if negotiated.get_u16() < min.get_u16() => {
+            Err(Error::authentication_error(
+                "TLS",
+                &format!(
+                    "the connection negotiated {:?}, but at least {:?} is required",
+                    negotiated, min
+                ),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Handshake replies keyed by server address, each of which is considered fresh for a fixed
+/// window after it was received. Clones share the same underlying cache.
+#[derive(Clone, Debug)]
+pub(crate) struct HandshakeReplyCache {
+    ttl: Duration,
+    replies: Arc<Mutex<HashMap<ServerAddress, (Instant, IsMasterReply)>>>,
+}
+
+impl HandshakeReplyCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            replies: Default::default(),
+        }
+    }
+
+    /// Returns the cached reply for the given address if it is still fresh, evicting it otherwise.
+    async fn get(&self, address: &ServerAddress) -> Option<IsMasterReply> {
+        let mut replies = self.replies.lock().await;
+        match replies.get(address) {
+            Some((received, reply)) if received.elapsed() < self.ttl => Some(reply.clone()),
+            Some(_) => {
+                replies.remove(address);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn insert(&self, address: ServerAddress, reply: IsMasterReply) {
+        self.replies
+            .lock()
+            .await
+            .insert(address, (Instant::now(), reply));
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
 pub(crate) struct HandshakerOptions {
     app_name: Option<String>,
     credential: Option<Credential>,
     driver_info: Option<DriverInfo>,
+
+    /// Overrides the base driver version reported in the handshake metadata, which otherwise is
+    /// the version of this crate.
+    driver_version: Option<String>,
     server_api: Option<ServerApi>,
     load_balanced: bool,
+    write_concern: Option<WriteConcern>,
+    strict_write_concern: bool,
+
+    /// Overrides the auto-incremented request id of the `isMaster` wire message, allowing the
+    /// handshake to be correlated with external logs.
+    request_id_hint: Option<i32>,
+
+    /// Servers whose maximum wire version is below this one are rejected during the handshake.
+    min_server_version: Option<WireVersion>,
+
+    /// A cache of handshake replies shared with other handshakers, used to skip the `isMaster`
+    /// round trip when a fresh reply for the same server is available.
+    reply_cache: Option<HandshakeReplyCache>,
+
+    /// Invoked with a summary of each successful handshake.
+    #[derivative(Debug = "ignore")]
+    on_handshake_complete: Option<HandshakeCallback>,
+
+    /// A document to send verbatim as the `client` metadata of the handshake, replacing the
+    /// metadata the driver would otherwise assemble, including the driver name and version and any
+    /// `app_name` or `driver_info`.
+    ///
+    /// This is dangerous: servers log and validate this document, and an incorrect one can make
+    /// the client misidentify itself or cause the handshake to be rejected. It is only intended for
+    /// compatibility testing. The document is not checked for the fields the handshake spec
+    /// requires.
+    full_metadata_override: Option<Document>,
+
+    /// Whether to restrict authentication to FIPS-approved mechanisms. SCRAM-SHA-1 relies on SHA-1,
+    /// which is not approved, so it is rejected both when specified by the credential and when it
+    /// would be selected by mechanism negotiation.
+    fips_mode: bool,
+
+    /// The maximum time to wait for the `isMaster` exchange of the handshake. Unlike
+    /// `connect_timeout`, this covers the exchange itself rather than opening the socket.
+    is_master_timeout: Option<Duration>,
+
+    /// The maximum time to wait for authentication after the `isMaster` exchange, including any
+    /// remaining SCRAM rounds. Timing out the two phases separately shows whether server discovery
+    /// or authentication is slow.
+    auth_timeout: Option<Duration>,
+
+    /// The handler to notify of the server check performed by the handshake. This is only set for
+    /// monitoring connections, since the handshakes of pooled connections are not server checks.
+    #[derivative(Debug = "ignore")]
+    sdam_event_handler: Option<Arc<dyn SdamEventHandler>>,
+
+    /// The database to send the handshake to, replacing the credential's auth source.
+    ///
+    /// This only changes where the `isMaster` command is sent. Authentication, including the
+    /// `saslSupportedMechs` negotiation and any speculative authentication in the handshake, still
+    /// uses the credential's source; to authenticate against a different database, set the
+    /// credential's `source` instead.
+    target_db_override: Option<String>,
+
+    /// Verifies the certificate presented by each replica set member against the set name the
+    /// member reported, as a defense-in-depth check on top of TLS certificate validation. A
+    /// mismatch fails the handshake with an authentication error.
+    #[derivative(Debug = "ignore")]
+    cert_matches_set: Option<CertMatchesSet>,
+
+    /// Transforms the application name before it is sent in the handshake metadata, e.g. to remove
+    /// personal information that shouldn't reach the server. Control characters are always
+    /// removed from the name, whether or not a sanitizer is configured.
+    #[derivative(Debug = "ignore")]
+    app_name_sanitizer: Option<AppNameSanitizer>,
+
+    /// The oldest TLS protocol version connections may use, e.g. `TLSv1_2` for compliance
+    /// requirements. A TLS connection that negotiated an older version fails the handshake with an
+    /// authentication error before anything is sent over it. Connections that don't use TLS are
+    /// not checked.
+    min_tls_version: Option<ProtocolVersion>,
+
+    /// Whether to check that the driver name in the handshake metadata still starts with
+    /// `mongo-rust-driver` once any `driver_info` or metadata override has been applied, which
+    /// catches wrapping libraries that replace the driver name rather than appending to it. If the
+    /// check fails, every handshake fails with an invalid argument error.
+    validate_driver_name: bool,
+
+    /// Whether to include the name of the machine the driver is running on in the handshake
+    /// metadata, under the `x-hostname` field, for identifying clients in server logs. It is the
+    /// first field dropped if the metadata would exceed the size limit.
+    include_hostname: bool,
+
+    /// Whether to leave the `platform` field, which describes the Rust compiler and async runtime
+    /// the driver was built with, out of the handshake metadata entirely, including any platform
+    /// appended through `driver_info`.
+    omit_platform: bool,
+
+    /// Encodes the client metadata sent in the handshake, replacing the default encoding of every
+    /// field. Unlike with `full_metadata_override`, the metadata is still assembled by the driver,
+    /// but the encoder decides what to send. Since an encoder may omit fields the handshake spec
+    /// requires, its output is not checked in debug builds.
+    #[derivative(Debug = "ignore")]
+    metadata_encoder: Option<Arc<dyn MetadataEncoder>>,
+
+    /// The authentication mechanisms to attempt speculative authentication for. When set, a
+    /// credential using any other mechanism is authenticated in full after the handshake instead,
+    /// which can be useful for mechanisms whose speculative conversation a deployment handles
+    /// poorly. If unset, speculative authentication is attempted for every mechanism that
+    /// supports it.
+    speculative_auth_mechanisms: Option<HashSet<AuthMechanism>>,
+
+    /// The version of the framework the application is built on, e.g. `2.3`, sent in the
+    /// handshake metadata as `application.version` to correlate connections with framework
+    /// upgrades. It is only sent along with an application name, and is dropped before any other
+    /// field except the hostname if the metadata would exceed the size limit.
+    framework_version: Option<String>,
+
+    /// Fields to add to the body of the handshake command, outside of the `client` metadata, e.g.
+    /// to experiment with a server build that accepts a new handshake field. Fields the driver
+    /// sets itself, such as `client` or `speculativeAuthenticate`, and fields starting with `$`
+    /// are ignored.
+    extra_command_fields: Option<Document>,
 }
 
 impl From<ConnectionPoolOptions> for HandshakerOptions {
@@ -255,8 +1310,31 @@ impl From<ConnectionPoolOptions> for HandshakerOptions {
             app_name: options.app_name,
             credential: options.credential,
             driver_info: options.driver_info,
+            driver_version: options.driver_version,
             server_api: options.server_api,
             load_balanced: options.load_balanced.unwrap_or(false),
+            write_concern: options.write_concern,
+            strict_write_concern: options.strict_write_concern.unwrap_or(false),
+            request_id_hint: options.handshake_request_id,
+            min_server_version: options.min_server_wire_version.map(WireVersion),
+            reply_cache: options.handshake_reply_cache,
+            on_handshake_complete: options.on_handshake_complete,
+            full_metadata_override: options.handshake_metadata_override,
+            fips_mode: options.fips_mode.unwrap_or(false),
+            is_master_timeout: options.handshake_timeout,
+            auth_timeout: options.auth_timeout,
+            sdam_event_handler: None,
+            target_db_override: options.handshake_target_db,
+            cert_matches_set: options.cert_matches_set,
+            app_name_sanitizer: options.app_name_sanitizer,
+            min_tls_version: options.min_tls_version,
+            validate_driver_name: options.validate_driver_name.unwrap_or(false),
+            include_hostname: options.include_hostname.unwrap_or(false),
+            omit_platform: options.omit_platform.unwrap_or(false),
+            metadata_encoder: options.metadata_encoder,
+            speculative_auth_mechanisms: options.speculative_auth_mechanisms,
+            framework_version: options.framework_version,
+            extra_command_fields: options.handshake_extra_fields,
         }
     }
 }
@@ -267,16 +1345,175 @@ impl From<ClientOptions> for HandshakerOptions {
             app_name: options.app_name,
             credential: options.credential,
             driver_info: options.driver_info,
+            driver_version: options.driver_version,
             server_api: options.server_api,
             load_balanced: options.load_balanced.unwrap_or(false),
+            write_concern: options.write_concern,
+            strict_write_concern: options.strict_write_concern.unwrap_or(false),
+            request_id_hint: options.handshake_request_id,
+            min_server_version: options.min_server_wire_version.map(WireVersion),
+            reply_cache: options.handshake_reply_cache,
+            on_handshake_complete: None,
+            full_metadata_override: options.handshake_metadata_override,
+            fips_mode: options.fips_mode.unwrap_or(false),
+            is_master_timeout: options.handshake_timeout,
+            auth_timeout: options.auth_timeout,
+            sdam_event_handler: options.sdam_event_handler,
+            target_db_override: options.handshake_target_db,
+            cert_matches_set: options.cert_matches_set,
+            app_name_sanitizer: options.app_name_sanitizer,
+            min_tls_version: options.min_tls_version,
+            validate_driver_name: options.validate_driver_name.unwrap_or(false),
+            include_hostname: options.include_hostname.unwrap_or(false),
+            omit_platform: options.omit_platform.unwrap_or(false),
+            metadata_encoder: options.metadata_encoder,
+            speculative_auth_mechanisms: options.speculative_auth_mechanisms,
+            framework_version: options.framework_version,
+            extra_command_fields: options.handshake_extra_fields,
+        }
+    }
+}
+
+/// Checks that the driver name in the metadata of the given handshake command starts with the
+/// name of this driver, returning a description of the problem otherwise.
+fn check_driver_name(command: &Command) -> std::result::Result<(), String> {
+    let base_name = BASE_CLIENT_METADATA.driver.name.as_str();
+    let name = command
+        .body
+        .get_document("client")
+        .and_then(|client| client.get_document("driver"))
+        .and_then(|driver| driver.get_str("name"))
+        .unwrap_or_default();
+
+    if !name.starts_with(base_name) {
+        return Err(format!(
+            "the handshake driver name {:?} does not start with {:?}; driver info should be \
+             appended to the driver name rather than replace it",
+            name, base_name
+        ));
+    }
+
+    Ok(())
+}
+
+/// The fields of a handshake command that are set by the driver, and so can't be overridden by
+/// `extra_command_fields`.
+const RESERVED_HANDSHAKE_FIELDS: &[&str] = &[
+    "apiDeprecationErrors",
+    "apiStrict",
+    "apiVersion",
+    "client",
+    "compression",
+    "hello",
+    "helloOk",
+    "isMaster",
+    "loadBalanced",
+    "lsid",
+    "saslSupportedMechs",
+    "speculativeAuthenticate",
+];
+
+/// The fields other than the command name that may appear in a handshake command.
+const ALLOWED_HANDSHAKE_FIELDS: &[&str] = &[
+    "client",
+    "compression",
+    "helloOk",
+    "loadBalanced",
+    "saslSupportedMechs",
+    "speculativeAuthenticate",
+];
+
+/// Checks that the given handshake command contains the fields required by the handshake spec and
+/// nothing else, returning a description of the first problem found.
+pub(crate) fn validate_handshake_command(command: &Command) -> std::result::Result<(), String> {
+    let mut keys = command.body.keys();
+    match keys.next() {
+        Some(name) if name == &command.name && (name == "hello" || name == "isMaster") => {}
+        other => {
+            return Err(format!(
+                "expected the first field to be the command name {:?}, got {:?}",
+                command.name, other
+            ))
+        }
+    }
+
+    if let Some(forbidden) = keys.find(|key| !ALLOWED_HANDSHAKE_FIELDS.contains(&key.as_str())) {
+        return Err(format!("unexpected field {:?}", forbidden));
+    }
+
+    let client = command
+        .body
+        .get_document("client")
+        .map_err(|_| "missing `client` metadata document".to_string())?;
+    let driver = client
+        .get_document("driver")
+        .map_err(|_| "missing `client.driver` document".to_string())?;
+    for field in &["name", "version"] {
+        if driver.get_str(field).is_err() {
+            return Err(format!("missing `client.driver.{}` string", field));
         }
     }
+    if client
+        .get_document("os")
+        .and_then(|os| os.get_str("type"))
+        .is_err()
+    {
+        return Err("missing `client.os.type` string".to_string());
+    }
+
+    Ok(())
+}
+
+/// Creates the error returned when a phase of connection establishment does not complete within its
+/// timeout. This is a network timeout, so it is handled like any other timed-out read.
+fn phase_timeout_error(phase: &str, timeout: Duration) -> Error {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!(
+            "the {} phase of the handshake timed out after {}ms",
+            phase,
+            timeout.as_millis()
+        ),
+    )
+    .into()
+}
+
+/// Converts a legacy `isMaster` command into the equivalent `hello` command, preserving the rest
+/// of the command body.
+fn hello_command(mut command: Command) -> Command {
+    let mut body = doc! { "hello": 1 };
+    body.extend(
+        std::mem::take(&mut command.body)
+            .into_iter()
+            .filter(|(key, _)| key.as_str() != "isMaster"),
+    );
+
+    command.name = "hello".to_string();
+    command.body = body;
+    command
+}
+
+/// Converts a `hello` command into the equivalent legacy `isMaster` command, preserving the rest
+/// of the command body.
+fn legacy_is_master_command(mut command: Command) -> Command {
+    let mut body = doc! { "isMaster": 1 };
+    body.extend(
+        std::mem::take(&mut command.body)
+            .into_iter()
+            .filter(|(key, _)| key.as_str() != "hello"),
+    );
+
+    command.name = "isMaster".to_string();
+    command.body = body;
+    command
 }
 
 /// Updates the handshake command document with the speculative authenitication info.
 fn set_speculative_auth_info(
     command: &mut Document,
     credential: Option<&Credential>,
+    fips_mode: bool,
+    allowed_mechanisms: Option<&HashSet<AuthMechanism>>,
 ) -> Result<Option<ClientFirst>> {
     let credential = match credential {
         Some(credential) => credential,
@@ -291,6 +1528,19 @@ fn set_speculative_auth_info(
         .as_ref()
         .unwrap_or(&AuthMechanism::ScramSha256);
 
+    if fips_mode && auth_mechanism == &AuthMechanism::ScramSha1 {
+        return Err(Error::authentication_error(
+            AuthMechanism::ScramSha1.as_str(),
+            "SCRAM-SHA-1 is not permitted in FIPS mode; use SCRAM-SHA-256 or MONGODB-X509 instead",
+        ));
+    }
+
+    if let Some(allowed_mechanisms) = allowed_mechanisms {
+        if !allowed_mechanisms.contains(auth_mechanism) {
+            return Ok(None);
+        }
+    }
+
     let client_first = match auth_mechanism.build_speculative_client_first(credential)? {
         Some(client_first) => client_first,
         None => return Ok(None),
@@ -1,8 +1,58 @@
-use super::Handshaker;
+use std::{
+    collections::HashSet,
+    io::Read,
+    net::TcpListener,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use super::{
+    check_cert_matches_set,
+    check_tls_version,
+    local_hostname,
+    set_speculative_auth_info,
+    AppMetadata,
+    AppNameSanitizer,
+    CertMatchesSet,
+    ClientMetadata,
+    ContainerMetadata,
+    EnvMetadata,
+    ENV_METADATA,
+    ENV_PROBES,
+    HandshakeCallback,
+    HandshakeReplyCache,
+    HandshakeResult,
+    HandshakeSummary,
+    Handshaker,
+    HandshakerOptions,
+    MetadataEncoder,
+    BASE_CLIENT_METADATA,
+    MAX_METADATA_SIZE,
+};
 use crate::{
-    bson::doc,
-    cmap::options::ConnectionPoolOptions,
-    options::{ClientOptions, DriverInfo},
+    bson::{doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Document, Timestamp},
+    cmap::{options::ConnectionPoolOptions, Connection, Message, StreamDescription},
+    error::ErrorKind,
+    event::sdam::{
+        SdamEventHandler,
+        ServerHeartbeatFailedEvent,
+        ServerHeartbeatStartedEvent,
+        ServerHeartbeatSucceededEvent,
+    },
+    options::{
+        Acknowledgment,
+        AuthMechanism,
+        ClientOptions,
+        Credential,
+        DriverInfo,
+        ServerAddress,
+        ServerApi,
+        ServerApiVersion,
+        WriteConcern,
+    },
+    runtime::AsyncStream,
+    sdam::ServerType,
+    RUNTIME,
 };
 
 #[test]
@@ -63,3 +113,1435 @@ fn metadata_with_options() {
     assert_eq!(os.get_str("type"), Ok(std::env::consts::OS));
     assert_eq!(os.get_str("architecture"), Ok(std::env::consts::ARCH));
 }
+
+#[test]
+fn replay_captured_sharded_reply() {
+    // Reply captured from a mongos in a 4.4 sharded cluster.
+    let reply = doc! {
+        "ismaster": true,
+        "msg": "isdbgrid",
+        "maxBsonObjectSize": 16777216,
+        "maxMessageSizeBytes": 48000000,
+        "maxWriteBatchSize": 100000,
+        "localTime": DateTime::now(),
+        "logicalSessionTimeoutMinutes": 30,
+        "connectionId": 7813,
+        "maxWireVersion": 9,
+        "minWireVersion": 0,
+        "topologyVersion": {
+            "processId": ObjectId::new(),
+            "counter": 0_i64,
+        },
+        "ok": 1.0,
+        "operationTime": Timestamp { time: 1623429530, increment: 1 },
+        "$clusterTime": {
+            "clusterTime": Timestamp { time: 1623429530, increment: 1 },
+            "signature": {
+                "hash": Binary {
+                    subtype: BinarySubtype::Generic,
+                    bytes: vec![0; 20],
+                },
+                "keyId": 0_i64,
+            },
+        },
+    };
+
+    let result = HandshakeResult::from_reply_document(reply).unwrap();
+    assert!(result.first_round.is_none());
+    assert!(result.is_master_reply.cluster_time.is_some());
+
+    let response = &result.is_master_reply.command_response;
+    assert_eq!(response.server_type(), ServerType::Mongos);
+    assert_eq!(response.max_wire_version, Some(9));
+    assert_eq!(response.logical_session_timeout_minutes, Some(30));
+
+    let description = StreamDescription::from_is_master(result.is_master_reply);
+    assert_eq!(description.initial_server_type, ServerType::Mongos);
+    assert!(description.is_mongos);
+    assert!(description.supports_retryable_writes());
+}
+
+#[test]
+fn mechanism_negotiation_target_uses_auth_source() {
+    let credential = Credential::builder()
+        .username("alice".to_string())
+        .password("pencil".to_string())
+        .source("users".to_string())
+        .build();
+    assert_eq!(credential.resolved_source(), "users");
+
+    let options = ClientOptions::builder()
+        .credential(credential.clone())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    assert_eq!(
+        handshaker.mechanism_negotiation_target(),
+        Some(format!("{}.{}", credential.resolved_source(), "alice").as_str())
+    );
+    assert_eq!(handshaker.command.target_db, "users");
+
+    // Mechanism negotiation is unnecessary when the mechanism is specified.
+    let credential = Credential {
+        mechanism: Some(AuthMechanism::ScramSha256),
+        ..credential
+    };
+    let options = ClientOptions::builder().credential(credential).build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    assert_eq!(handshaker.mechanism_negotiation_target(), None);
+}
+
+#[test]
+fn with_credential_swaps_only_auth_fields() {
+    let alice = Credential::builder()
+        .username("alice".to_string())
+        .password("pencil".to_string())
+        .source("users".to_string())
+        .build();
+    let options = ClientOptions::builder()
+        .credential(alice)
+        .app_name("tenants".to_string())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    let bob = Credential::builder()
+        .username("bob".to_string())
+        .password("hunter2".to_string())
+        .source("accounts".to_string())
+        .build();
+    let tenant = handshaker.with_credential(Some(bob.clone()));
+
+    assert_eq!(
+        tenant.command.body.get_document("client"),
+        handshaker.command.body.get_document("client")
+    );
+    assert_eq!(tenant.metadata_size, handshaker.metadata_size);
+
+    assert_eq!(handshaker.mechanism_negotiation_target(), Some("users.alice"));
+    assert_eq!(tenant.mechanism_negotiation_target(), Some("accounts.bob"));
+    assert_eq!(handshaker.command.target_db, "users");
+    assert_eq!(tenant.command.target_db, "accounts");
+    assert_eq!(tenant.credential, Some(bob));
+
+    // Without a credential, there is nothing to negotiate and the handshake goes to admin.
+    let anonymous = tenant.with_credential(None);
+    assert_eq!(anonymous.mechanism_negotiation_target(), None);
+    assert_eq!(anonymous.command.target_db, "admin");
+    assert!(anonymous.credential.is_none());
+}
+
+#[test]
+fn is_mongos_parsed_from_msg() {
+    let reply = |msg: Option<&str>| {
+        let mut reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+        };
+        if let Some(msg) = msg {
+            reply.insert("msg", msg);
+        }
+        let result = HandshakeResult::from_reply_document(reply).unwrap();
+        StreamDescription::from_is_master(result.is_master_reply)
+    };
+
+    assert!(reply(Some("isdbgrid")).is_mongos);
+    assert!(!reply(Some("something else")).is_mongos);
+    assert!(!reply(None).is_mongos);
+}
+
+#[test]
+fn replay_captured_error_reply() {
+    let reply = doc! {
+        "ok": 0.0,
+        "errmsg": "not authorized on admin",
+        "code": 13,
+        "codeName": "Unauthorized",
+    };
+
+    HandshakeResult::from_reply_document(reply).expect_err("error reply should fail to replay");
+}
+
+#[test]
+fn write_concern_checked_against_wire_version() {
+    // MongoDB 3.2 only supports up to wire version 4.
+    let reply = doc! {
+        "ok": 1.0,
+        "ismaster": true,
+        "maxBsonObjectSize": 16777216,
+        "maxWriteBatchSize": 1000,
+        "maxWireVersion": 4,
+        "minWireVersion": 0,
+    };
+    let is_master_reply = HandshakeResult::from_reply_document(reply)
+        .unwrap()
+        .is_master_reply;
+
+    let majority = WriteConcern::builder()
+        .w(Acknowledgment::Majority)
+        .w_timeout(Duration::from_secs(1))
+        .build();
+
+    let handshaker = |write_concern: WriteConcern, strict: bool| {
+        let options = ClientOptions::builder()
+            .write_concern(write_concern)
+            .strict_write_concern(strict)
+            .build();
+        Handshaker::new(Some(options.into()))
+    };
+
+    let error = handshaker(majority.clone(), true)
+        .handshake_result(is_master_reply.clone(), None)
+        .expect_err("strict mode should reject an unsupported write concern");
+    assert!(matches!(*error.kind, ErrorKind::IncompatibleServer { .. }));
+
+    handshaker(majority, false)
+        .handshake_result(is_master_reply.clone(), None)
+        .expect("the write concern is only checked in strict mode");
+
+    handshaker(WriteConcern::builder().w(Acknowledgment::Nodes(1)).build(), true)
+        .handshake_result(is_master_reply, None)
+        .expect("a supported write concern should pass in strict mode");
+}
+
+#[test]
+fn server_below_min_server_version_rejected() {
+    // MongoDB 3.6 supports up to wire version 6.
+    let reply = doc! {
+        "ok": 1.0,
+        "ismaster": true,
+        "maxBsonObjectSize": 16777216,
+        "maxWriteBatchSize": 100000,
+        "maxWireVersion": 6,
+        "minWireVersion": 0,
+    };
+    let is_master_reply = HandshakeResult::from_reply_document(reply)
+        .unwrap()
+        .is_master_reply;
+
+    let handshaker = |min_server_wire_version: i32| {
+        let options = ClientOptions::builder()
+            .min_server_wire_version(min_server_wire_version)
+            .build();
+        Handshaker::new(Some(options.into()))
+    };
+
+    let error = handshaker(8)
+        .handshake_result(is_master_reply.clone(), None)
+        .expect_err("server below the minimum version should be rejected");
+    match *error.kind {
+        ErrorKind::IncompatibleServer { ref message } => {
+            assert!(message.contains("minimum server version policy"));
+            assert!(message.contains("MongoDB 3.6"));
+            assert!(message.contains("MongoDB 4.2"));
+        }
+        ref other => panic!("expected IncompatibleServer error, got {:?}", other),
+    }
+
+    handshaker(6)
+        .handshake_result(is_master_reply, None)
+        .expect("server at the minimum version should be accepted");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn dropped_handshake_marks_connection_errored() {
+    // The listener's backlog completes the TCP handshake, but nothing ever responds to the
+    // `isMaster`, so the handshake will never complete.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = ServerAddress::Tcp {
+        host: "127.0.0.1".to_string(),
+        port: Some(listener.local_addr().unwrap().port()),
+    };
+
+    let mut conn = Connection::new_testing(1, address, 0, None).await.unwrap();
+    assert!(!conn.has_errored());
+
+    let handshaker = Handshaker::new(None);
+    RUNTIME
+        .timeout(Duration::from_millis(100), handshaker.handshake(&mut conn))
+        .await
+        .expect_err("handshake should not complete");
+
+    assert!(conn.has_errored());
+
+    // The same applies when the handshake's own timeout cuts the `isMaster` exchange short.
+    let mut conn = Connection::new_testing(2, conn.address().clone(), 0, None)
+        .await
+        .unwrap();
+    let options = ClientOptions::builder()
+        .handshake_timeout(Duration::from_millis(100))
+        .build();
+    let error = Handshaker::new(Some(options.into()))
+        .handshake(&mut conn)
+        .await
+        .expect_err("handshake should time out");
+    assert!(error.is_network_timeout(), "{}", error);
+    assert!(conn.has_errored());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn request_id_hint_used_for_handshake() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = ServerAddress::Tcp {
+        host: "127.0.0.1".to_string(),
+        port: Some(listener.local_addr().unwrap().port()),
+    };
+
+    // Read the header of the incoming message and close the connection without replying. The
+    // request id follows the 4-byte message length.
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut header = [0u8; 16];
+        stream.read_exact(&mut header).unwrap();
+        i32::from_le_bytes([header[4], header[5], header[6], header[7]])
+    });
+
+    let mut conn = Connection::new_testing(1, address, 0, None).await.unwrap();
+
+    let options = ClientOptions::builder()
+        .handshake_request_id(424242)
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    handshaker
+        .handshake(&mut conn)
+        .await
+        .expect_err("server never replies");
+
+    assert_eq!(server.join().unwrap(), 424242);
+}
+
+#[test]
+fn speculative_auth_restricted_to_configured_mechanisms() {
+    let preview = |credential: Credential| {
+        let mechanisms: HashSet<_> = vec![AuthMechanism::ScramSha256].into_iter().collect();
+        let options = ClientOptions::builder()
+            .credential(credential)
+            .speculative_auth_mechanisms(mechanisms)
+            .build();
+        let handshaker = Handshaker::new(Some(options.into()));
+        let mut body = handshaker.command.body.clone();
+        set_speculative_auth_info(
+            &mut body,
+            handshaker.credential.as_ref(),
+            handshaker.fips_mode,
+            handshaker.speculative_auth_mechanisms.as_ref(),
+        )
+        .unwrap();
+        body
+    };
+
+    let x509 = Credential::builder()
+        .mechanism(AuthMechanism::MongoDbX509)
+        .build();
+    assert!(!preview(x509).contains_key("speculativeAuthenticate"));
+
+    let scram = Credential::builder()
+        .username("user".to_string())
+        .password("pencil".to_string())
+        .mechanism(AuthMechanism::ScramSha256)
+        .build();
+    let speculative = preview(scram);
+    let speculative = speculative.get_document("speculativeAuthenticate").unwrap();
+    assert_eq!(speculative.get_str("mechanism"), Ok("SCRAM-SHA-256"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handshake_over_custom_stream() {
+    // An in-memory stream standing in for a Unix domain socket.
+    let (client, mut server) = duplex();
+
+    let server = async move {
+        let (request_id, command) = Message::read_command(&mut server).await.unwrap();
+        let reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+        };
+        Message::write_reply(&mut server, request_id, &reply)
+            .await
+            .unwrap();
+
+        command
+    };
+
+    let address = ServerAddress::Tcp {
+        host: "/tmp/mongodb-27017.sock".to_string(),
+        port: None,
+    };
+    let mut conn = Connection::with_stream(1, address, 0, client, None);
+    let handshaker = Handshaker::new(None);
+
+    let (command, result) =
+        futures_util::future::join(server, handshaker.handshake(&mut conn)).await;
+
+    assert!(command.contains_key("isMaster"));
+    let result = result.unwrap();
+    assert_eq!(
+        result.is_master_reply.command_response.server_type(),
+        ServerType::Standalone
+    );
+    assert_eq!(conn.stream_description().unwrap().max_wire_version, Some(9));
+}
+
+/// Creates an in-memory transport, returning the end to hand to a `Connection` and the end for
+/// the test to act as the server on.
+fn duplex() -> (tokio::io::DuplexStream, AsyncStream) {
+    let (client, server) = tokio::io::duplex(16 * 1024);
+    (client, AsyncStream::Custom(Box::new(server)))
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn hello_falls_back_to_legacy_is_master() {
+    let (client, mut server) = duplex();
+
+    // An old server that only understands the legacy command name.
+    let server = async move {
+        let (request_id, hello) = Message::read_command(&mut server).await.unwrap();
+        Message::write_reply(
+            &mut server,
+            request_id,
+            &doc! {
+                "ok": 0.0,
+                "errmsg": "no such command: 'hello'",
+                "code": 59,
+                "codeName": "CommandNotFound",
+            },
+        )
+        .await
+        .unwrap();
+
+        let (request_id, is_master) = Message::read_command(&mut server).await.unwrap();
+        Message::write_reply(
+            &mut server,
+            request_id,
+            &doc! {
+                "ok": 1.0,
+                "ismaster": true,
+                "maxBsonObjectSize": 16777216,
+                "maxWriteBatchSize": 100000,
+                "maxWireVersion": 6,
+                "minWireVersion": 0,
+            },
+        )
+        .await
+        .unwrap();
+
+        (hello, is_master)
+    };
+
+    let options = ClientOptions::builder()
+        .server_api(ServerApi::builder().version(ServerApiVersion::V1).build())
+        .build();
+    let address = ServerAddress::Tcp {
+        host: "localhost".to_string(),
+        port: None,
+    };
+    let mut conn = Connection::with_stream(1, address, 0, client, None);
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    let ((hello, is_master), result) =
+        futures_util::future::join(server, handshaker.handshake(&mut conn)).await;
+
+    assert_eq!(hello.keys().next().map(String::as_str), Some("hello"));
+    assert_eq!(is_master.keys().next().map(String::as_str), Some("isMaster"));
+    assert!(!is_master.contains_key("hello"));
+    assert!(is_master.contains_key("client"));
+
+    result.expect("legacy isMaster should succeed");
+    assert_eq!(conn.stream_description().unwrap().max_wire_version, Some(6));
+}
+
+#[test]
+fn metadata_with_driver_version_override() {
+    let options = ConnectionPoolOptions::from_client_options(
+        &ClientOptions::builder()
+            .driver_version("v2.0.0-pinned".to_string())
+            .driver_info(
+                DriverInfo::builder()
+                    .name("odm".to_string())
+                    .version("1.2.3".to_string())
+                    .build(),
+            )
+            .build(),
+    );
+
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    let metadata = handshaker.command.body.get_document("client").unwrap();
+    let driver = metadata.get_document("driver").unwrap();
+    assert_eq!(driver.get_str("name"), Ok("mongo-rust-driver|odm"));
+    assert_eq!(driver.get_str("version"), Ok("v2.0.0-pinned|1.2.3"));
+}
+
+#[test]
+fn handshake_command_validation() {
+    use super::validate_handshake_command;
+
+    let credential = Credential::builder()
+        .username("user".to_string())
+        .password("pencil".to_string())
+        .build();
+    let mut options: HandshakerOptions = ClientOptions::builder()
+        .credential(credential)
+        .build()
+        .into();
+    options.load_balanced = true;
+    let handshaker = Handshaker::new(Some(options));
+    validate_handshake_command(&handshaker.command).expect("well-formed command should pass");
+
+    let mut with_forbidden = handshaker.command.clone();
+    with_forbidden.body.insert("getMore", 1_i64);
+    let error = validate_handshake_command(&with_forbidden).unwrap_err();
+    assert!(error.contains("getMore"), "{}", error);
+
+    let mut without_client = handshaker.command.clone();
+    without_client.body.remove("client");
+    let error = validate_handshake_command(&without_client).unwrap_err();
+    assert!(error.contains("client"), "{}", error);
+
+    let mut wrong_name = handshaker.command;
+    wrong_name.name = "getMore".to_string();
+    assert!(validate_handshake_command(&wrong_name).is_err());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn cached_reply_used_within_window() {
+    let address = ServerAddress::Tcp {
+        host: "localhost".to_string(),
+        port: Some(27017),
+    };
+    let reply = doc! {
+        "ok": 1.0,
+        "ismaster": true,
+        "maxBsonObjectSize": 16777216,
+        "maxWriteBatchSize": 100000,
+        "maxWireVersion": 9,
+        "minWireVersion": 0,
+    };
+
+    let handshaker_with_cache = |cache: &HandshakeReplyCache| {
+        let mut options = ClientOptions::builder().build();
+        options.handshake_reply_cache = Some(cache.clone());
+        Handshaker::new(Some(options.into()))
+    };
+
+    // The first connection runs a full handshake, populating the cache.
+    let cache = HandshakeReplyCache::new(Duration::from_secs(60));
+    let (client, mut server) = duplex();
+    let server = async move {
+        let (request_id, _) = Message::read_command(&mut server).await.unwrap();
+        Message::write_reply(&mut server, request_id, &reply)
+            .await
+            .unwrap();
+        reply
+    };
+    let mut conn = Connection::with_stream(1, address.clone(), 0, client, None);
+    let (reply, result) = futures_util::future::join(
+        server,
+        handshaker_with_cache(&cache).handshake(&mut conn),
+    )
+    .await;
+    result.unwrap();
+
+    // The second connection's peer never responds, so the handshake can only succeed from the
+    // cache.
+    let (client, _server) = duplex();
+    let mut conn = Connection::with_stream(2, address.clone(), 0, client, None);
+    let result = RUNTIME
+        .timeout(
+            Duration::from_secs(1),
+            handshaker_with_cache(&cache).handshake(&mut conn),
+        )
+        .await
+        .expect("cached reply should be used")
+        .unwrap();
+    assert_eq!(result.is_master_reply.command_response.max_wire_version, Some(9));
+    assert_eq!(conn.stream_description().unwrap().max_wire_version, Some(9));
+
+    // Once the window has passed, the cached reply is no longer used.
+    let expired = HandshakeReplyCache::new(Duration::from_secs(0));
+    expired
+        .insert(
+            address.clone(),
+            HandshakeResult::from_reply_document(reply)
+                .unwrap()
+                .is_master_reply,
+        )
+        .await;
+    let (client, _server) = duplex();
+    let mut conn = Connection::with_stream(3, address, 0, client, None);
+    RUNTIME
+        .timeout(
+            Duration::from_millis(100),
+            handshaker_with_cache(&expired).handshake(&mut conn),
+        )
+        .await
+        .expect_err("expired reply should not be used");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handshake_complete_callback_invoked() {
+    let (client, mut server) = duplex();
+    let server = async move {
+        let (request_id, _) = Message::read_command(&mut server).await.unwrap();
+        Message::write_reply(
+            &mut server,
+            request_id,
+            &doc! {
+                "ok": 1.0,
+                "ismaster": true,
+                "maxBsonObjectSize": 16777216,
+                "maxWriteBatchSize": 100000,
+                "maxWireVersion": 9,
+                "minWireVersion": 0,
+            },
+        )
+        .await
+        .unwrap();
+    };
+
+    let summaries = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let client_options = ClientOptions::builder()
+        .on_handshake_complete({
+            let summaries = summaries.clone();
+            Arc::new(move |summary: HandshakeSummary| summaries.lock().unwrap().push(summary))
+                as HandshakeCallback
+        })
+        .build();
+    let options = ConnectionPoolOptions::from_client_options(&client_options);
+
+    let address = ServerAddress::Tcp {
+        host: "localhost".to_string(),
+        port: Some(27017),
+    };
+    let mut conn = Connection::with_stream(1, address.clone(), 0, client, None);
+    let handshaker = Handshaker::new(Some(options.into()));
+    let (_, result) = futures_util::future::join(server, handshaker.handshake(&mut conn)).await;
+    result.unwrap();
+
+    let summaries = summaries.lock().unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].address, address);
+    assert!(summaries[0].duration > Duration::from_secs(0));
+    assert!(!summaries[0].speculative_auth_succeeded);
+    assert_eq!(summaries[0].auth_mechanism, None);
+}
+
+#[test]
+fn handshake_summary_displayed_on_one_line() {
+    let mut summary = HandshakeSummary {
+        address: ServerAddress::Tcp {
+            host: "db1.example.com".to_string(),
+            port: Some(27018),
+        },
+        duration: Duration::from_millis(12),
+        server_type: ServerType::RsPrimary,
+        max_wire_version: Some(13),
+        auth_mechanism: Some(AuthMechanism::ScramSha256),
+        speculative_auth_succeeded: true,
+        metadata_size: 200,
+    };
+    assert_eq!(
+        summary.to_string(),
+        "handshake with db1.example.com:27018: type RsPrimary, wire version 13, auth \
+         SCRAM-SHA-256, rtt 12ms"
+    );
+
+    summary.max_wire_version = None;
+    summary.auth_mechanism = None;
+    let formatted = summary.to_string();
+    assert!(formatted.contains("wire version unknown"), "{}", formatted);
+    assert!(formatted.contains("no auth"), "{}", formatted);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handshake_summary_records_metadata_size() {
+    let (client, mut server) = duplex();
+    let server = async move {
+        let (request_id, command) = Message::read_command(&mut server).await.unwrap();
+        Message::write_reply(
+            &mut server,
+            request_id,
+            &doc! {
+                "ok": 1.0,
+                "ismaster": true,
+                "maxBsonObjectSize": 16777216,
+                "maxWriteBatchSize": 100000,
+                "maxWireVersion": 9,
+                "minWireVersion": 0,
+            },
+        )
+        .await
+        .unwrap();
+        command
+    };
+
+    let summaries = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut options = ConnectionPoolOptions::from_client_options(
+        &ClientOptions::builder().app_name("metrics".to_string()).build(),
+    );
+    options.on_handshake_complete = Some({
+        let summaries = summaries.clone();
+        Arc::new(move |summary: HandshakeSummary| summaries.lock().unwrap().push(summary))
+    });
+
+    let mut conn = Connection::with_stream(1, ServerAddress::default(), 0, client, None);
+    let handshaker = Handshaker::new(Some(options.into()));
+    let (command, result) =
+        futures_util::future::join(server, handshaker.handshake(&mut conn)).await;
+    result.unwrap();
+
+    let sent = crate::bson::to_vec(command.get_document("client").unwrap()).unwrap();
+    let summaries = summaries.lock().unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].metadata_size, sent.len());
+    assert!(summaries[0].metadata_size <= MAX_METADATA_SIZE);
+}
+
+#[test]
+fn empty_sasl_supported_mechs_rejected() {
+    let reply = |mechanisms: Vec<&str>| {
+        let reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+            "saslSupportedMechs": mechanisms,
+        };
+        HandshakeResult::from_reply_document(reply)
+            .unwrap()
+            .is_master_reply
+    };
+
+    let credential = Credential::builder()
+        .username("x509user".to_string())
+        .password("pencil".to_string())
+        .build();
+    let handshaker = Handshaker::new(Some(
+        ClientOptions::builder()
+            .credential(credential)
+            .build()
+            .into(),
+    ));
+
+    let error = handshaker
+        .handshake_result(reply(Vec::new()), None)
+        .expect_err("empty mechanism list should fail the handshake");
+    assert!(matches!(*error.kind, ErrorKind::Authentication { .. }));
+    assert!(error.to_string().contains("admin.x509user"), "{}", error);
+
+    handshaker
+        .handshake_result(reply(vec!["SCRAM-SHA-256"]), None)
+        .expect("advertised mechanism should be accepted");
+
+    // Without mechanism negotiation, the reply's mechanisms are irrelevant.
+    Handshaker::new(None)
+        .handshake_result(reply(Vec::new()), None)
+        .expect("no negotiation was requested");
+}
+
+#[test]
+fn full_metadata_override_used_verbatim() {
+    let metadata = doc! {
+        "driver": { "name": "PyMongo", "version": "3.12.0" },
+        "os": { "type": "Linux" },
+        "platform": "CPython 3.9.5",
+    };
+
+    let options = ClientOptions::builder()
+        .app_name("ignored".to_string())
+        .driver_info(DriverInfo::builder().name("ignored".to_string()).build())
+        .handshake_metadata_override(metadata.clone())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    assert_eq!(
+        handshaker.command.body.get_document("client"),
+        Ok(&metadata)
+    );
+
+    // Metadata missing fields the handshake spec requires is still sent as given.
+    let incomplete = doc! { "driver": { "name": "PyMongo" } };
+    let options = ClientOptions::builder()
+        .handshake_metadata_override(incomplete.clone())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    assert_eq!(
+        handshaker.command.body.get_document("client"),
+        Ok(&incomplete)
+    );
+}
+
+#[test]
+fn target_db_override_leaves_auth_source() {
+    let credential = Credential::builder()
+        .username("alice".to_string())
+        .password("pencil".to_string())
+        .source("users".to_string())
+        .build();
+    let options = ClientOptions::builder()
+        .credential(credential)
+        .handshake_target_db("tenant_42".to_string())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    assert_eq!(handshaker.command.target_db, "tenant_42");
+
+    // Authentication still happens against the credential's source.
+    assert_eq!(handshaker.mechanism_negotiation_target(), Some("users.alice"));
+    let mut body = handshaker.command.body.clone();
+    set_speculative_auth_info(
+        &mut body,
+        handshaker.credential.as_ref(),
+        handshaker.fips_mode,
+        handshaker.speculative_auth_mechanisms.as_ref(),
+    )
+    .unwrap();
+    let speculative = body.get_document("speculativeAuthenticate").unwrap();
+    assert_eq!(speculative.get_str("db"), Ok("users"));
+}
+
+#[test]
+fn fips_mode_rejects_scram_sha_1() {
+    let reply = |mechanisms: Vec<&str>| {
+        let reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+            "saslSupportedMechs": mechanisms,
+        };
+        HandshakeResult::from_reply_document(reply)
+            .unwrap()
+            .is_master_reply
+    };
+    let handshaker = |mechanism: Option<AuthMechanism>| {
+        let credential = Credential::builder()
+            .username("user".to_string())
+            .password("pencil".to_string())
+            .mechanism(mechanism)
+            .build();
+        let options = ClientOptions::builder()
+            .credential(credential)
+            .fips_mode(true)
+            .build();
+        Handshaker::new(Some(options.into()))
+    };
+
+    let error = handshaker(None)
+        .handshake_result(reply(vec!["SCRAM-SHA-1"]), None)
+        .expect_err("SCRAM-SHA-1 should not be negotiated in FIPS mode");
+    assert!(matches!(*error.kind, ErrorKind::Authentication { .. }));
+    assert!(error.to_string().contains("FIPS"), "{}", error);
+
+    handshaker(None)
+        .handshake_result(reply(vec!["SCRAM-SHA-1", "SCRAM-SHA-256"]), None)
+        .expect("SCRAM-SHA-256 should be negotiated in FIPS mode");
+
+    let speculative_auth = |handshaker: Handshaker| {
+        let mut body = handshaker.command.body.clone();
+        set_speculative_auth_info(
+            &mut body,
+            handshaker.credential.as_ref(),
+            handshaker.fips_mode,
+            handshaker.speculative_auth_mechanisms.as_ref(),
+        )
+        .map(|_| ())
+    };
+    let error = speculative_auth(handshaker(Some(AuthMechanism::ScramSha1)))
+        .expect_err("an explicit SCRAM-SHA-1 credential should be rejected in FIPS mode");
+    assert!(matches!(*error.kind, ErrorKind::Authentication { .. }));
+    speculative_auth(handshaker(Some(AuthMechanism::ScramSha256)))
+        .expect("SCRAM-SHA-256 should be allowed in FIPS mode");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handshake_phases_time_out_separately() {
+    let timeout = Duration::from_millis(50);
+    let handshaker = |handshake_timeout: Option<Duration>, auth_timeout: Option<Duration>| {
+        let options = ClientOptions::builder()
+            .handshake_timeout(handshake_timeout)
+            .auth_timeout(auth_timeout)
+            .build();
+        Handshaker::new(Some(options.into()))
+    };
+
+    // A server that reads the `isMaster` command but never replies.
+    let (client, mut server) = duplex();
+    let server = RUNTIME.spawn(async move {
+        Message::read_command(&mut server).await.unwrap();
+        RUNTIME.delay_for(Duration::from_secs(1)).await;
+    });
+    let mut conn = Connection::with_stream(1, ServerAddress::default(), 0, client, None);
+    let error = handshaker(Some(timeout), None)
+        .handshake(&mut conn)
+        .await
+        .expect_err("the isMaster phase should time out");
+    assert!(error.is_network_timeout());
+    assert!(error.to_string().contains("isMaster phase"), "{}", error);
+    drop(server);
+
+    let slow_auth = || async {
+        RUNTIME.delay_for(Duration::from_millis(200)).await;
+        Ok(())
+    };
+
+    // The `isMaster` timeout does not apply to authentication.
+    handshaker(Some(timeout), None)
+        .authenticate_with_timeout(slow_auth())
+        .await
+        .expect("authentication should not be subject to the isMaster timeout");
+
+    let error = handshaker(None, Some(timeout))
+        .authenticate_with_timeout(slow_auth())
+        .await
+        .expect_err("the authentication phase should time out");
+    assert!(error.is_network_timeout());
+    assert!(
+        error.to_string().contains("authentication phase"),
+        "{}",
+        error
+    );
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handshake_emits_heartbeat_events() {
+    #[derive(Debug)]
+    enum Heartbeat {
+        Started(ServerHeartbeatStartedEvent),
+        Succeeded(ServerHeartbeatSucceededEvent),
+        Failed(ServerHeartbeatFailedEvent),
+    }
+
+    #[derive(Default)]
+    struct HeartbeatRecorder {
+        events: std::sync::Mutex<Vec<Heartbeat>>,
+    }
+
+    impl SdamEventHandler for HeartbeatRecorder {
+        fn handle_server_heartbeat_started_event(&self, event: ServerHeartbeatStartedEvent) {
+            self.events.lock().unwrap().push(Heartbeat::Started(event));
+        }
+
+        fn handle_server_heartbeat_succeeded_event(&self, event: ServerHeartbeatSucceededEvent) {
+            self.events.lock().unwrap().push(Heartbeat::Succeeded(event));
+        }
+
+        fn handle_server_heartbeat_failed_event(&self, event: ServerHeartbeatFailedEvent) {
+            self.events.lock().unwrap().push(Heartbeat::Failed(event));
+        }
+    }
+
+    let recorder = Arc::new(HeartbeatRecorder::default());
+    let client_options = ClientOptions::builder()
+        .sdam_event_handler(recorder.clone() as Arc<dyn SdamEventHandler>)
+        .build();
+    let reply = doc! {
+        "ok": 1.0,
+        "ismaster": true,
+        "maxBsonObjectSize": 16777216,
+        "maxWriteBatchSize": 100000,
+        "maxWireVersion": 9,
+        "minWireVersion": 0,
+    };
+
+    let handshake = |handshaker: Handshaker, reply: Document| async move {
+        let (client, mut server) = duplex();
+        let server = async move {
+            let (request_id, _) = Message::read_command(&mut server).await.unwrap();
+            Message::write_reply(&mut server, request_id, &reply)
+                .await
+                .unwrap();
+        };
+        let mut conn = Connection::with_stream(1, ServerAddress::default(), 0, client, None);
+        let (result, _) = futures::join!(handshaker.handshake(&mut conn), server);
+        result
+    };
+
+    // The handshake of a monitoring connection is reported as a heartbeat.
+    handshake(
+        Handshaker::new(Some(client_options.clone().into())),
+        reply.clone(),
+    )
+    .await
+    .unwrap();
+    {
+        let events = recorder.events.lock().unwrap();
+        match events.as_slice() {
+            [Heartbeat::Started(started), Heartbeat::Succeeded(succeeded)] => {
+                assert_eq!(started.server_address, ServerAddress::default());
+                assert!(!started.awaited);
+                assert_eq!(succeeded.server_address, ServerAddress::default());
+                assert_eq!(succeeded.reply, reply);
+                assert!(!succeeded.awaited);
+            }
+            other => panic!("expected started and succeeded events, got {:?}", other),
+        }
+    }
+
+    recorder.events.lock().unwrap().clear();
+    let error_reply = doc! { "ok": 0.0, "code": 11600, "errmsg": "interrupted at shutdown" };
+    handshake(
+        Handshaker::new(Some(client_options.clone().into())),
+        error_reply,
+    )
+    .await
+    .expect_err("handshake should fail");
+    {
+        let events = recorder.events.lock().unwrap();
+        match events.as_slice() {
+            [Heartbeat::Started(_), Heartbeat::Failed(failed)] => {
+                assert_eq!(failed.failure.code(), Some(11600));
+                assert!(!failed.awaited);
+            }
+            other => panic!("expected started and failed events, got {:?}", other),
+        }
+    }
+
+    // Pooled connections are not server checks, so their handshakes aren't reported.
+    recorder.events.lock().unwrap().clear();
+    let pool_options = ConnectionPoolOptions::from_client_options(&client_options);
+    handshake(Handshaker::new(Some(pool_options.into())), reply)
+        .await
+        .unwrap();
+    assert!(recorder.events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn cert_matches_set_checked_against_set_name() {
+    let reply = |set_name: Option<&str>| {
+        let mut reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 13,
+            "minWireVersion": 0,
+        };
+        if let Some(set_name) = set_name {
+            reply.insert("setName", set_name);
+        }
+        HandshakeResult::from_reply_document(reply)
+            .unwrap()
+            .is_master_reply
+            .command_response
+    };
+    // A stub certificate whose "subject" is the name of the set it was issued for.
+    let cert_matches_set: CertMatchesSet =
+        Arc::new(|set_name: &str, cert: &[u8]| cert == set_name.as_bytes());
+    let options = ClientOptions::builder()
+        .cert_matches_set(cert_matches_set)
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    let cert_matches_set = handshaker.cert_matches_set.as_ref();
+
+    check_cert_matches_set(cert_matches_set, &reply(Some("rs0")), Some(b"rs0"))
+        .expect("the certificate was issued for rs0");
+
+    let error = check_cert_matches_set(cert_matches_set, &reply(Some("rs0")), Some(b"rs1"))
+        .expect_err("the certificate was issued for another set");
+    assert!(matches!(*error.kind, ErrorKind::Authentication { .. }));
+    assert!(error.to_string().contains("rs0"), "{}", error);
+
+    // Standalones, connections without TLS, and handshakers without a check are not verified.
+    check_cert_matches_set(cert_matches_set, &reply(None), Some(b"rs1")).unwrap();
+    check_cert_matches_set(cert_matches_set, &reply(Some("rs0")), None).unwrap();
+    check_cert_matches_set(None, &reply(Some("rs0")), Some(b"rs1")).unwrap();
+}
+
+#[test]
+fn tls_version_below_minimum_rejected() {
+    use rustls::ProtocolVersion;
+
+    // rustls never negotiates anything older than TLS 1.2, so the negotiated version is stubbed.
+    let options = ClientOptions::builder()
+        .min_tls_version(ProtocolVersion::TLSv1_2)
+        .build();
+    let min = Handshaker::new(Some(options.into())).min_tls_version;
+    let error = check_tls_version(min, Some(ProtocolVersion::TLSv1_0)).unwrap_err();
+    assert!(matches!(*error.kind, ErrorKind::Authentication { .. }));
+
+    assert!(check_tls_version(min, Some(ProtocolVersion::TLSv1_2)).is_ok());
+    assert!(check_tls_version(min, Some(ProtocolVersion::TLSv1_3)).is_ok());
+    assert!(check_tls_version(None, Some(ProtocolVersion::TLSv1_0)).is_ok());
+
+    // Connections without TLS aren't checked.
+    assert!(check_tls_version(min, None).is_ok());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn driver_name_validated() {
+    let handshaker = |driver_info: DriverInfo, metadata_override: Option<Document>| {
+        let options = ClientOptions::builder()
+            .driver_info(driver_info)
+            .handshake_metadata_override(metadata_override)
+            .validate_driver_name(true)
+            .build();
+        Handshaker::new(Some(options.into()))
+    };
+    let driver_info = || DriverInfo::builder().name("wrapper".to_string()).build();
+
+    // Driver info is appended to the driver name, so it passes validation.
+    let handshaker_with_info = handshaker(driver_info(), None);
+    assert!(handshaker_with_info.invalid_driver_name.is_none());
+
+    // An override that drops the base driver name fails every handshake before any I/O.
+    let metadata = doc! {
+        "driver": { "name": "wrapper", "version": "1.0.0" },
+        "os": { "type": "Linux" },
+    };
+    let (client, _server) = duplex();
+    let mut conn = Connection::with_stream(1, ServerAddress::default(), 0, client, None);
+    let error = handshaker(driver_info(), Some(metadata))
+        .handshake(&mut conn)
+        .await
+        .expect_err("the driver name should be rejected");
+    assert!(matches!(*error.kind, ErrorKind::InvalidArgument { .. }));
+    assert!(error.to_string().contains("mongo-rust-driver"), "{}", error);
+}
+
+#[test]
+fn app_name_sanitized() {
+    let app_name = |sanitizer: Option<AppNameSanitizer>| {
+        let options = ClientOptions::builder()
+            .app_name("orders\nuser=alice@example.com\t".to_string())
+            .app_name_sanitizer(sanitizer)
+            .build();
+        let handshaker = Handshaker::new(Some(options.into()));
+        handshaker
+            .command
+            .body
+            .get_document("client")
+            .unwrap()
+            .get_document("application")
+            .unwrap()
+            .get_str("name")
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(app_name(None), "ordersuser=alice@example.com");
+
+    // The sanitizer only sees the name once control characters are removed.
+    let redact: AppNameSanitizer = Arc::new(|name: String| match name.find("user=") {
+        Some(index) => format!("{}user=<redacted>", &name[..index]),
+        None => name,
+    });
+    assert_eq!(app_name(Some(redact)), "ordersuser=<redacted>");
+}
+
+#[test]
+fn container_reported_in_env() {
+    assert_eq!(ContainerMetadata::detect(false, false), None);
+    assert_eq!(
+        ContainerMetadata::detect(true, false),
+        Some(ContainerMetadata {
+            runtime: Some("docker".to_string()),
+            orchestrator: None,
+        })
+    );
+
+    let container = ContainerMetadata::detect(true, true);
+    let mut metadata = BASE_CLIENT_METADATA.clone();
+    metadata.env = Some(EnvMetadata { container });
+    let metadata = match Bson::from(metadata) {
+        Bson::Document(metadata) => metadata,
+        other => panic!("expected a document, got {:?}", other),
+    };
+    let container = metadata
+        .get_document("env")
+        .and_then(|env| env.get_document("container"))
+        .unwrap();
+    assert_eq!(container.get_str("orchestrator"), Ok("kubernetes"));
+    assert_eq!(container.get_str("runtime"), Ok("docker"));
+
+    // The environment is dropped right after the hostname when the metadata is too large.
+    let mut metadata = BASE_CLIENT_METADATA.clone();
+    metadata.env = Some(EnvMetadata {
+        container: ContainerMetadata::detect(false, true),
+    });
+    metadata.application = Some(AppMetadata {
+        name: "a".repeat(MAX_METADATA_SIZE - metadata.encoded_size()),
+        version: None,
+    });
+    metadata.truncate();
+    assert_eq!(metadata.env, None);
+    assert_eq!(metadata.os.architecture, BASE_CLIENT_METADATA.os.architecture);
+}
+
+#[test]
+fn env_probed_once() {
+    let handshakers: Vec<_> = (0..1000).map(|_| Handshaker::new(None)).collect();
+    for handshaker in &handshakers {
+        let env = handshaker
+            .command
+            .body
+            .get_document("client")
+            .unwrap()
+            .get_document("env")
+            .ok();
+        assert_eq!(env.is_some(), ENV_METADATA.is_some());
+    }
+    assert_eq!(ENV_PROBES.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn platform_omitted_from_metadata() {
+    let driver_info = DriverInfo::builder()
+        .name("wrapper".to_string())
+        .platform("wrapper-platform".to_string())
+        .build();
+    let metadata = |omit_platform: bool| {
+        let options = ClientOptions::builder()
+            .driver_info(driver_info.clone())
+            .omit_platform(omit_platform)
+            .build();
+        let handshaker = Handshaker::new(Some(options.into()));
+        handshaker
+            .command
+            .body
+            .get_document("client")
+            .unwrap()
+            .clone()
+    };
+
+    let platform = metadata(false).get_str("platform").unwrap().to_string();
+    assert!(platform.ends_with("|wrapper-platform"), "{}", platform);
+
+    let metadata = metadata(true);
+    assert!(!metadata.contains_key("platform"), "{:?}", metadata);
+    assert!(metadata.get_document("driver").is_ok());
+}
+
+#[test]
+fn hostname_included_in_metadata() {
+    let metadata = |include_hostname: bool| {
+        let options = ClientOptions::builder()
+            .include_hostname(include_hostname)
+            .build();
+        let handshaker = Handshaker::new(Some(options.into()));
+        handshaker
+            .command
+            .body
+            .get_document("client")
+            .unwrap()
+            .clone()
+    };
+
+    assert!(!metadata(false).contains_key("x-hostname"));
+    assert_eq!(
+        metadata(true).get_str("x-hostname").ok(),
+        local_hostname().as_deref()
+    );
+
+    // The hostname is the first field dropped when the metadata is too large.
+    let mut metadata = BASE_CLIENT_METADATA.clone();
+    metadata.hostname = Some("h".repeat(MAX_METADATA_SIZE - metadata.encoded_size()));
+    metadata.truncate();
+    assert_eq!(metadata.hostname, None);
+    assert_eq!(metadata.platform, BASE_CLIENT_METADATA.platform);
+    assert_eq!(metadata.os.architecture, BASE_CLIENT_METADATA.os.architecture);
+
+    // Short hostnames are kept.
+    let mut metadata = BASE_CLIENT_METADATA.clone();
+    metadata.hostname = Some("db-client-1".to_string());
+    metadata.truncate();
+    assert_eq!(metadata.hostname.as_deref(), Some("db-client-1"));
+}
+
+#[test]
+fn monitoring_command_omits_metadata() {
+    let options = ClientOptions::builder()
+        .app_name("monitored".to_string())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    // The initial handshake of a monitoring connection sends the metadata.
+    assert!(handshaker.command.body.contains_key("client"));
+
+    // Later checks on the same connection don't.
+    let command = handshaker.monitoring_command(false);
+    assert_eq!(command.name, handshaker.command.name);
+    assert_eq!(command.body.keys().next(), handshaker.command.body.keys().next());
+    assert!(!command.body.contains_key("client"));
+    assert_eq!(command.target_db, "admin");
+
+    // Once the server has acknowledged `helloOk`, checks use `hello`.
+    let command = handshaker.monitoring_command(true);
+    assert_eq!(command.name, "hello");
+    assert_eq!(command.body.keys().next().map(String::as_str), Some("hello"));
+    assert!(!command.body.contains_key("isMaster"));
+    assert!(!command.body.contains_key("client"));
+}
+
+#[test]
+fn extra_command_fields_merged() {
+    let options = ClientOptions::builder()
+        .handshake_extra_fields(doc! {
+            "experimentalRouting": { "zone": "a" },
+            "client": "overridden",
+            "isMaster": 0,
+            "$db": "other",
+        })
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    let body = &handshaker.command.body;
+
+    assert_eq!(
+        body.get_document("experimentalRouting"),
+        Ok(&doc! { "zone": "a" })
+    );
+    assert_eq!(body.keys().next().map(String::as_str), Some("isMaster"));
+    assert_eq!(body.get_i32("isMaster"), Ok(1));
+    assert!(body.get_document("client").is_ok());
+    assert!(!body.contains_key("$db"));
+    assert_eq!(handshaker.command.target_db, "admin");
+}
+
+#[test]
+fn framework_version_included_under_application() {
+    let options = ClientOptions::builder()
+        .app_name("orders".to_string())
+        .framework_version("2.3".to_string())
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+    let client = handshaker.command.body.get_document("client").unwrap();
+    assert_eq!(
+        client.get_document("application").unwrap(),
+        &doc! { "name": "orders", "version": "2.3" }
+    );
+
+    // The version is dropped after the hostname when the metadata is too large.
+    let mut metadata = BASE_CLIENT_METADATA.clone();
+    metadata.application = Some(AppMetadata {
+        name: "orders".to_string(),
+        version: Some("v".repeat(MAX_METADATA_SIZE - metadata.encoded_size())),
+    });
+    metadata.hostname = Some("db-client-1".to_string());
+    metadata.truncate();
+    assert_eq!(metadata.hostname, None);
+    assert_eq!(metadata.application.unwrap().version, None);
+    assert_eq!(metadata.os.architecture, BASE_CLIENT_METADATA.os.architecture);
+}
+
+#[test]
+fn last_write_parsed_from_reply() {
+    let last_write_date = DateTime::from_millis(1_600_000_000_000);
+    let reply = doc! {
+        "ok": 1.0,
+        "ismaster": false,
+        "secondary": true,
+        "setName": "rs0",
+        "maxBsonObjectSize": 16777216,
+        "maxWriteBatchSize": 100000,
+        "maxWireVersion": 13,
+        "minWireVersion": 0,
+        "lastWrite": {
+            "opTime": { "ts": Timestamp { time: 1_600_000_000, increment: 3 }, "t": 7_i64 },
+            "lastWriteDate": last_write_date,
+            "majorityOpTime": { "ts": Timestamp { time: 1_600_000_000, increment: 1 }, "t": 7_i64 },
+            "majorityWriteDate": last_write_date,
+        },
+    };
+
+    let result = HandshakeResult::from_reply_document(reply).unwrap();
+    let last_write = result
+        .is_master_reply
+        .command_response
+        .last_write
+        .expect("lastWrite should be parsed");
+    assert_eq!(last_write.last_write_date, last_write_date);
+
+    let op_time = last_write.op_time.expect("opTime should be parsed");
+    assert_eq!(
+        op_time.ts,
+        Timestamp {
+            time: 1_600_000_000,
+            increment: 3
+        }
+    );
+    assert_eq!(op_time.t, 7);
+}
+
+#[test]
+fn hello_ok_negotiated() {
+    let handshaker = Handshaker::new(None);
+    assert_eq!(handshaker.command.body.get_bool("helloOk"), Ok(true));
+
+    let reply = |hello_ok: Option<bool>| {
+        let mut reply = doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 13,
+            "minWireVersion": 0,
+        };
+        if let Some(hello_ok) = hello_ok {
+            reply.insert("helloOk", hello_ok);
+        }
+        HandshakeResult::from_reply_document(reply).unwrap()
+    };
+
+    assert!(reply(Some(true)).hello_ok);
+    assert!(!reply(Some(false)).hello_ok);
+    // Servers that predate `hello` don't echo the flag.
+    assert!(!reply(None).hello_ok);
+}
+
+#[test]
+fn custom_metadata_encoder() {
+    struct WithoutOs;
+
+    impl MetadataEncoder for WithoutOs {
+        fn encode(&self, metadata: &ClientMetadata) -> Bson {
+            let mut encoded = doc! {
+                "driver": {
+                    "name": metadata.driver.name.clone(),
+                    "version": metadata.driver.version.clone(),
+                },
+            };
+            if let Some(ref application) = metadata.application {
+                encoded.insert("application", doc! { "name": application.name.clone() });
+            }
+            encoded.into()
+        }
+    }
+
+    let options = ClientOptions::builder()
+        .app_name("encoded".to_string())
+        .metadata_encoder(Arc::new(WithoutOs) as Arc<dyn MetadataEncoder>)
+        .build();
+    let handshaker = Handshaker::new(Some(options.into()));
+
+    let metadata = handshaker.command.body.get_document("client").unwrap();
+    assert!(!metadata.contains_key("os"));
+    assert!(!metadata.contains_key("platform"));
+    assert_eq!(
+        metadata.get_document("application"),
+        Ok(&doc! { "name": "encoded" })
+    );
+    assert_eq!(
+        metadata.get_document("driver").unwrap().get_str("name"),
+        Ok("mongo-rust-driver")
+    );
+}
@@ -2,6 +2,16 @@ pub(super) mod handshake;
 #[cfg(test)]
 mod test;
 
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::Instant,
+};
+
 use self::handshake::Handshaker;
 use super::{
     conn::{ConnectionGeneration, PendingConnection},
@@ -11,7 +21,8 @@ use super::{
 };
 use crate::{
     client::{auth::Credential, options::ServerApi},
-    error::{Error as MongoError, ErrorKind},
+    error::{Error as MongoError, ErrorKind, Result},
+    options::{CircuitBreakerOptions, ServerAddress},
     runtime::HttpClient,
     sdam::HandshakePhase,
 };
@@ -25,11 +36,22 @@ pub(super) struct ConnectionEstablisher {
     http_client: HttpClient,
     credential: Option<Credential>,
     server_api: Option<ServerApi>,
+
+    /// Set when the pool is draining. Once set, new connections will not be established, but
+    /// handshakes that have already started are allowed to finish.
+    draining: Arc<AtomicBool>,
+
+    /// Stops handshakes with the pool's server after repeated failures, if configured.
+    circuit_breaker: Option<CircuitBreaker>,
 }
 
 impl ConnectionEstablisher {
     /// Creates a new ConnectionEstablisher from the given options.
-    pub(super) fn new(http_client: HttpClient, options: Option<&ConnectionPoolOptions>) -> Self {
+    pub(super) fn new(
+        http_client: HttpClient,
+        options: Option<&ConnectionPoolOptions>,
+        draining: Arc<AtomicBool>,
+    ) -> Self {
         let handshaker = Handshaker::new(options.cloned().map(Into::into));
 
         Self {
@@ -37,6 +59,10 @@ impl ConnectionEstablisher {
             http_client,
             credential: options.and_then(|options| options.credential.clone()),
             server_api: options.and_then(|options| options.server_api.clone()),
+            draining,
+            circuit_breaker: options
+                .and_then(|options| options.circuit_breaker.clone())
+                .map(CircuitBreaker::new),
         }
     }
 
@@ -46,15 +72,36 @@ impl ConnectionEstablisher {
         pending_connection: PendingConnection,
     ) -> std::result::Result<Connection, EstablishError> {
         let pool_gen = pending_connection.generation.clone();
+
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(EstablishError::pre_hello(
+                ErrorKind::ConnectionPoolCleared {
+                    message: format!(
+                        "Connection pool for {} was cleared because the server was removed from \
+                         the topology",
+                        pending_connection.address
+                    ),
+                }
+                .into(),
+                pool_gen,
+            ));
+        }
+
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker
+                .check(&pending_connection.address)
+                .map_err(|e| EstablishError::pre_hello(e, pool_gen.clone()))?;
+        }
+
         let mut connection = Connection::connect(pending_connection)
             .await
             .map_err(|e| EstablishError::pre_hello(e, pool_gen.clone()))?;
 
-        let handshake = self
-            .handshaker
-            .handshake(&mut connection)
-            .await
-            .map_err(|e| EstablishError::pre_hello(e, pool_gen.clone()))?;
+        let handshake = self.handshaker.handshake(&mut connection).await;
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker.record(handshake.is_ok());
+        }
+        let handshake = handshake.map_err(|e| EstablishError::pre_hello(e, pool_gen.clone()))?;
         let service_id = handshake.is_master_reply.command_response.service_id;
 
         // If the handshake response had a `serviceId` field, this is a connection to a load
@@ -79,21 +126,123 @@ impl ConnectionEstablisher {
         }
 
         if let Some(ref credential) = self.credential {
-            credential
-                .authenticate_stream(
+            let result = self
+                .handshaker
+                .authenticate_with_timeout(credential.authenticate_stream(
                     &mut connection,
                     &self.http_client,
                     self.server_api.as_ref(),
                     handshake.first_round,
-                )
-                .await
-                .map_err(|e| EstablishError::post_hello(e, connection.generation.clone()))?
+                ))
+                .await;
+            result.map_err(|e| EstablishError::post_hello(e, connection.generation.clone()))?
         }
 
         Ok(connection)
     }
 }
 
+/// A circuit breaker around the handshakes with a single server. It opens after
+/// `failure_threshold` consecutive handshake failures within `window`, rejecting new connection
+/// attempts until `cool_down` has passed. It then half-opens, letting a single trial handshake
+/// through: if that succeeds the breaker closes, and otherwise it opens again.
+///
+/// Clones share the same state.
+#[derive(Clone, Debug)]
+struct CircuitBreaker {
+    options: CircuitBreakerOptions,
+    state: Arc<Mutex<CircuitBreakerState>>,
+}
+
+#[derive(Debug)]
+enum CircuitBreakerState {
+    /// Handshakes are allowed. Contains the times of the consecutive failures within the window.
+    Closed { failures: VecDeque<Instant> },
+
+    /// Handshakes are rejected until the given time.
+    Open { until: Instant },
+
+    /// A trial handshake started at the given time is in progress, and others are rejected.
+    HalfOpen { trial_started: Instant },
+}
+
+impl CircuitBreaker {
+    fn new(options: CircuitBreakerOptions) -> Self {
+        Self {
+            options,
+            state: Arc::new(Mutex::new(CircuitBreakerState::Closed {
+                failures: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Returns an error if a handshake with the server should not be attempted right now.
+    fn check(&self, address: &ServerAddress) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let trial_allowed_at = match *state {
+            CircuitBreakerState::Closed { .. } => return Ok(()),
+            CircuitBreakerState::Open { until } => until,
+            // A trial that never reported back (e.g. because it was dropped) is replaced by a new
+            // one after another cool-down.
+            CircuitBreakerState::HalfOpen { trial_started } => {
+                trial_started + self.options.cool_down
+            }
+        };
+
+        if now >= trial_allowed_at {
+            *state = CircuitBreakerState::HalfOpen { trial_started: now };
+            return Ok(());
+        }
+
+        Err(ErrorKind::ServerSelection {
+            message: format!(
+                "not connecting to {} after {} consecutive handshake failures; another attempt \
+                 will be allowed once the {}ms cool-down has passed",
+                address,
+                self.options.failure_threshold,
+                self.options.cool_down.as_millis()
+            ),
+        }
+        .into())
+    }
+
+    /// Records the outcome of a handshake.
+    fn record(&self, succeeded: bool) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if succeeded {
+            *state = CircuitBreakerState::Closed {
+                failures: VecDeque::new(),
+            };
+            return;
+        }
+
+        let open = match *state {
+            CircuitBreakerState::Closed { ref mut failures } => {
+                while let Some(failure) = failures.front() {
+                    if now.duration_since(*failure) <= self.options.window {
+                        break;
+                    }
+                    failures.pop_front();
+                }
+                failures.push_back(now);
+                failures.len() >= self.options.failure_threshold as usize
+            }
+            CircuitBreakerState::HalfOpen { .. } => true,
+            CircuitBreakerState::Open { .. } => false,
+        };
+
+        if open {
+            *state = CircuitBreakerState::Open {
+                until: now + self.options.cool_down,
+            };
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct EstablishError {
     pub(crate) cause: MongoError,
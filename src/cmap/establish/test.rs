@@ -1,10 +1,41 @@
+use std::{
+    io::Read,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+        Arc,
+        Mutex,
+    },
+    time::Duration,
+};
+
 use tokio::sync::RwLockWriteGuard;
 
+use super::ConnectionEstablisher;
 use crate::{
     bson::{doc, Bson},
-    cmap::{establish::Handshaker, Command, Connection, ConnectionPoolOptions},
-    options::{AuthMechanism, ClientOptions, Credential, ReadPreference},
-    test::{TestClient, CLIENT_OPTIONS, LOCK},
+    cmap::{
+        conn::PendingConnection,
+        establish::Handshaker,
+        Command,
+        Connection,
+        ConnectionPoolOptions,
+        PoolGeneration,
+    },
+    error::ErrorKind,
+    options::{
+        AuthMechanism,
+        CircuitBreakerOptions,
+        ClientOptions,
+        Credential,
+        ReadPreference,
+        ServerAddress,
+        Tls,
+        TlsOptions,
+    },
+    test::{start_fake_server, TestClient, CLIENT_OPTIONS, LOCK},
+    RUNTIME,
 };
 
 async fn speculative_auth_test(
@@ -160,3 +191,208 @@ async fn speculative_auth_x509() {
     )
     .await;
 }
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn draining_rejects_new_connections() {
+    // Hold the `isMaster` reply until the test releases it, so that the first establishment is
+    // still mid-handshake when the pool starts draining.
+    let (received_sender, received_receiver) = tokio::sync::oneshot::channel();
+    let (release_sender, release_receiver) = mpsc::channel::<()>();
+    let received_sender = Mutex::new(Some(received_sender));
+    let release_receiver = Mutex::new(release_receiver);
+    let address = start_fake_server(move |_| {
+        if let Some(sender) = received_sender.lock().unwrap().take() {
+            sender.send(()).unwrap();
+        }
+        release_receiver.lock().unwrap().recv().unwrap();
+
+        Some(doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+        })
+    });
+
+    let draining = Arc::new(AtomicBool::new(false));
+    let establisher = ConnectionEstablisher::new(Default::default(), None, draining.clone());
+    let pending = |id| PendingConnection {
+        id,
+        address: address.clone(),
+        generation: PoolGeneration::normal(),
+        options: None,
+    };
+
+    let in_progress = establisher.establish_connection(pending(1));
+    let drain_then_establish = async {
+        received_receiver.await.unwrap();
+        draining.store(true, Ordering::SeqCst);
+
+        let error = establisher
+            .establish_connection(pending(2))
+            .await
+            .expect_err("establishment should be rejected while draining");
+        assert!(error.cause.is_pool_cleared());
+
+        release_sender.send(()).unwrap();
+    };
+
+    let (in_progress, _) = futures_util::future::join(in_progress, drain_then_establish).await;
+    in_progress.expect("in-progress handshake should complete");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn circuit_breaker_opens_after_repeated_handshake_failures() {
+    // Fail the first three handshakes by closing their connections, then reply to each
+    // `isMaster` after that.
+    let handshakes = AtomicUsize::new(0);
+    let address = start_fake_server(move |_| {
+        if handshakes.fetch_add(1, Ordering::SeqCst) < 3 {
+            return None;
+        }
+        Some(doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 9,
+            "minWireVersion": 0,
+        })
+    });
+
+    let cool_down = Duration::from_millis(100);
+    let client_options = ClientOptions::builder()
+        .circuit_breaker(
+            CircuitBreakerOptions::builder()
+                .failure_threshold(2)
+                .window(Duration::from_secs(10))
+                .cool_down(cool_down)
+                .build(),
+        )
+        .build();
+    let options = ConnectionPoolOptions::from_client_options(&client_options);
+    let establisher = ConnectionEstablisher::new(
+        Default::default(),
+        Some(&options),
+        Arc::new(AtomicBool::new(false)),
+    );
+    let establish = || {
+        establisher.establish_connection(PendingConnection {
+            id: 1,
+            address: address.clone(),
+            generation: PoolGeneration::normal(),
+            options: None,
+        })
+    };
+    let is_open = |result: Result<_, super::EstablishError>| match result {
+        Err(error) => matches!(*error.cause.kind, ErrorKind::ServerSelection { .. }),
+        Ok(_) => false,
+    };
+
+    // The breaker opens after two consecutive failures.
+    assert!(!is_open(establish().await));
+    assert!(!is_open(establish().await));
+    assert!(is_open(establish().await));
+
+    // After the cool-down, it half-opens to let a trial through, which fails and reopens it.
+    RUNTIME.delay_for(cool_down * 2).await;
+    assert!(!is_open(establish().await));
+    assert!(is_open(establish().await));
+
+    // A successful trial closes it.
+    RUNTIME.delay_for(cool_down * 2).await;
+    establish().await.expect("the trial handshake should succeed");
+    establish().await.expect("the breaker should be closed");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn tls_sni_override_presented() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = ServerAddress::Tcp {
+        host: "127.0.0.1".to_string(),
+        port: Some(listener.local_addr().unwrap().port()),
+    };
+
+    // Capture the TLS ClientHello record, then close the connection without replying.
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).unwrap();
+        let length = u16::from_be_bytes([header[3], header[4]]);
+        let mut client_hello = vec![0u8; length as usize];
+        stream.read_exact(&mut client_hello).unwrap();
+        client_hello
+    });
+
+    let client_options = ClientOptions::builder()
+        .tls(Tls::Enabled(TlsOptions::default()))
+        .tls_sni_override("proxy.example.com".to_string())
+        .build();
+    let options = ConnectionPoolOptions::from_client_options(&client_options);
+    let establisher = ConnectionEstablisher::new(
+        Default::default(),
+        Some(&options),
+        Arc::new(AtomicBool::new(false)),
+    );
+    establisher
+        .establish_connection(PendingConnection {
+            id: 1,
+            address,
+            generation: PoolGeneration::normal(),
+            options: Some(options.into()),
+        })
+        .await
+        .expect_err("server closes the connection");
+
+    let client_hello = server.join().unwrap();
+    let sni = b"proxy.example.com";
+    assert!(client_hello.windows(sni.len()).any(|window| window == sni));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn no_auth_negotiation_without_credential_on_localhost() {
+    // Reply to every command, sending each one back to the test.
+    let (sender, commands) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let server = start_fake_server(move |command| {
+        sender.lock().unwrap().send(command.clone()).unwrap();
+        Some(doc! {
+            "ok": 1.0,
+            "ismaster": true,
+            "maxBsonObjectSize": 16777216,
+            "maxWriteBatchSize": 100000,
+            "maxWireVersion": 13,
+            "minWireVersion": 0,
+        })
+    });
+    let address = ServerAddress::Tcp {
+        host: "localhost".to_string(),
+        port: server.port(),
+    };
+
+    let establisher =
+        ConnectionEstablisher::new(Default::default(), None, Arc::new(AtomicBool::new(false)));
+    let connection = establisher
+        .establish_connection(PendingConnection {
+            id: 1,
+            address,
+            generation: PoolGeneration::normal(),
+            options: None,
+        })
+        .await
+        .expect("the connection should be established without authenticating");
+    drop(connection);
+
+    // The handshake is the only command sent, and it doesn't negotiate authentication.
+    let handshake = commands.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(handshake.contains_key("isMaster"));
+    assert!(!handshake.contains_key("saslSupportedMechs"));
+    assert!(!handshake.contains_key("speculativeAuthenticate"));
+    assert!(commands.recv_timeout(Duration::from_secs(5)).is_err());
+}
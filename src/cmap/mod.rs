@@ -9,21 +9,44 @@ pub(crate) mod options;
 mod status;
 mod worker;
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use derivative::Derivative;
 
-pub use self::conn::ConnectionInfo;
+pub use self::{
+    conn::ConnectionInfo,
+    establish::handshake::{
+        AppMetadata,
+        AppNameSanitizer,
+        CertMatchesSet,
+        ClientMetadata,
+        ContainerMetadata,
+        DriverMetadata,
+        EnvMetadata,
+        HandshakeCallback,
+        HandshakeSummary,
+        MetadataEncoder,
+        OsMetadata,
+    },
+};
 pub(crate) use self::{
     conn::{Command, Connection, RawCommand, RawCommandResponse, StreamDescription},
-    establish::{handshake::Handshaker, EstablishError},
+    establish::{
+        handshake::{HandshakeReplyCache, Handshaker},
+        EstablishError,
+    },
     status::PoolGenerationSubscriber,
     worker::PoolGeneration,
 };
+#[cfg(test)]
+pub(crate) use self::conn::Message;
 use self::{connection_requester::ConnectionRequestResult, options::ConnectionPoolOptions};
 use crate::{
     bson::oid::ObjectId,
-    error::{Error, Result},
+    error::{Error, ErrorKind, Result},
     event::cmap::{
         CmapEventHandler,
         ConnectionCheckoutFailedEvent,
@@ -53,6 +76,7 @@ pub(crate) struct ConnectionPool {
     manager: PoolManager,
     connection_requester: ConnectionRequester,
     generation_subscriber: PoolGenerationSubscriber,
+    draining: Arc<AtomicBool>,
 
     #[derivative(Debug = "ignore")]
     event_handler: Option<Arc<dyn CmapEventHandler>>,
@@ -65,11 +89,13 @@ impl ConnectionPool {
         server_updater: ServerUpdateSender,
         options: Option<ConnectionPoolOptions>,
     ) -> Self {
+        let draining = Arc::new(AtomicBool::new(false));
         let (manager, connection_requester, generation_subscriber) = ConnectionPoolWorker::start(
             address.clone(),
             http_client,
             server_updater,
             options.clone(),
+            draining.clone(),
         );
 
         let event_handler = options.as_ref().and_then(|opts| opts.event_handler.clone());
@@ -86,6 +112,7 @@ impl ConnectionPool {
             manager,
             connection_requester,
             generation_subscriber,
+            draining,
             event_handler,
         }
     }
@@ -102,10 +129,19 @@ impl ConnectionPool {
             manager,
             connection_requester,
             generation_subscriber,
+            draining: Arc::new(AtomicBool::new(false)),
             event_handler: None,
         }
     }
 
+    /// Signals that the pool is shutting down. Any connection establishment that has not yet
+    /// begun will fail with `ErrorKind::ConnectionPoolCleared`, so that operations that selected
+    /// the server are retried on another one, while handshakes already in progress are allowed to
+    /// complete.
+    pub(crate) fn drain(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
     fn emit_event<F>(&self, emit: F)
     where
         F: FnOnce(&Arc<dyn CmapEventHandler>),
@@ -170,4 +206,20 @@ impl ConnectionPool {
     pub(crate) fn generation(&self) -> PoolGeneration {
         self.generation_subscriber.generation()
     }
+
+    /// Returns an error if the given connection was established under a generation of the pool
+    /// that has since been cleared.
+    pub(crate) fn check_generation(&self, connection: &Connection) -> Result<()> {
+        if connection.generation.is_stale(&self.generation()) {
+            return Err(ErrorKind::ConnectionPoolCleared {
+                message: format!(
+                    "Connection {} to {} was established before the pool was cleared",
+                    connection.id, self.address
+                ),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
 }
@@ -1,18 +1,37 @@
 #[cfg(test)]
 use std::cmp::Ordering;
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use derivative::Derivative;
+use rustls::ProtocolVersion;
 #[cfg(test)]
 use serde::de::{Deserializer, Error};
 use serde::Deserialize;
 use typed_builder::TypedBuilder;
 
+use super::establish::handshake::{
+    AppNameSanitizer,
+    CertMatchesSet,
+    HandshakeCallback,
+    HandshakeReplyCache,
+    MetadataEncoder,
+};
 use crate::{
+    bson::Document,
     bson_util,
-    client::{auth::Credential, options::ServerApi},
+    client::{
+        auth::{AuthMechanism, Credential},
+        options::ServerApi,
+    },
     event::cmap::{CmapEventHandler, ConnectionPoolOptions as EventOptions},
-    options::{ClientOptions, DriverInfo, ServerAddress, TlsOptions},
+    options::{
+        CircuitBreakerOptions,
+        ClientOptions,
+        DriverInfo,
+        ServerAddress,
+        TlsOptions,
+        WriteConcern,
+    },
 };
 
 /// Contains the options for creating a connection pool.
@@ -38,6 +57,10 @@ pub(crate) struct ConnectionPoolOptions {
     #[serde(skip)]
     pub(crate) driver_info: Option<DriverInfo>,
 
+    /// The base driver version to report in the metadata of the handshake with the server.
+    #[serde(skip)]
+    pub(crate) driver_version: Option<String>,
+
     /// Processes all events generated by the pool.
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
     #[serde(skip)]
@@ -57,6 +80,11 @@ pub(crate) struct ConnectionPoolOptions {
     #[serde(deserialize_with = "bson_util::deserialize_duration_option_from_u64_millis")]
     pub(crate) max_idle_time: Option<Duration>,
 
+    /// The maximum number of connections that the pool can be establishing at a given time.
+    ///
+    /// The default is 2.
+    pub(crate) max_connecting: Option<u32>,
+
     /// The maximum number of connections that the pool can have at a given time. This includes
     /// connections which are currently checked out of the pool.
     ///
@@ -87,8 +115,111 @@ pub(crate) struct ConnectionPoolOptions {
     #[serde(skip)]
     pub(crate) tls_options: Option<TlsOptions>,
 
+    /// The hostname to present via SNI when establishing TLS connections, in place of the host
+    /// being connected to, e.g. so that a TLS-terminating proxy routes connections correctly.
+    /// Certificates are also verified against this hostname.
+    ///
+    /// The default is to present the host being connected to.
+    #[serde(skip)]
+    pub(crate) tls_sni_override: Option<String>,
+
     /// Whether or not the client is connecting to a MongoDB cluster through a load balancer.
     pub(crate) load_balanced: Option<bool>,
+
+    /// The client's default write concern, which is checked against each server's wire version
+    /// during the handshake.
+    #[serde(skip)]
+    pub(crate) write_concern: Option<WriteConcern>,
+
+    /// Whether a write concern unsupported by the server should cause the handshake to fail.
+    #[serde(skip)]
+    pub(crate) strict_write_concern: Option<bool>,
+
+    /// The minimum wire version a server must support for the handshake to succeed.
+    #[serde(skip)]
+    pub(crate) min_server_wire_version: Option<i32>,
+
+    /// Whether to restrict authentication to FIPS-approved mechanisms.
+    #[serde(skip)]
+    pub(crate) fips_mode: Option<bool>,
+
+    /// The maximum time to wait for the handshake exchange of a new connection.
+    #[serde(skip)]
+    pub(crate) handshake_timeout: Option<Duration>,
+
+    /// The maximum time to wait for a new connection to authenticate after its handshake.
+    #[serde(skip)]
+    pub(crate) auth_timeout: Option<Duration>,
+
+    /// Whether to include the local hostname in the handshake metadata.
+    #[serde(skip)]
+    pub(crate) include_hostname: Option<bool>,
+
+    /// The version of the framework the application is built on, sent in the handshake metadata.
+    #[serde(skip)]
+    pub(crate) framework_version: Option<String>,
+
+    /// Whether to leave the `platform` field out of the handshake metadata.
+    #[serde(skip)]
+    pub(crate) omit_platform: Option<bool>,
+
+    /// Stops the pool from handshaking with a server that keeps failing handshakes, if set.
+    #[serde(skip)]
+    pub(crate) circuit_breaker: Option<CircuitBreakerOptions>,
+
+    /// Invoked with a summary of each completed handshake, for lightweight instrumentation that
+    /// doesn't require subscribing to CMAP events.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) on_handshake_complete: Option<HandshakeCallback>,
+
+    /// The request id to use for the wire message carrying the handshake of each new connection.
+    #[serde(skip)]
+    pub(crate) handshake_request_id: Option<i32>,
+
+    /// A cache of handshake replies shared by every handshaker of the Client, if enabled.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) handshake_reply_cache: Option<HandshakeReplyCache>,
+
+    /// A document to send verbatim as the `client` metadata of each handshake.
+    #[serde(skip)]
+    pub(crate) handshake_metadata_override: Option<Document>,
+
+    /// The database to send each handshake to, replacing the credential's auth source.
+    #[serde(skip)]
+    pub(crate) handshake_target_db: Option<String>,
+
+    /// Verifies the certificate presented by each replica set member against its set name.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) cert_matches_set: Option<CertMatchesSet>,
+
+    /// Whether to check that the driver name in the handshake metadata starts with this driver's.
+    #[serde(skip)]
+    pub(crate) validate_driver_name: Option<bool>,
+
+    /// Encodes the client metadata sent in each handshake.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) metadata_encoder: Option<Arc<dyn MetadataEncoder>>,
+
+    /// The authentication mechanisms to attempt speculative authentication for.
+    #[serde(skip)]
+    pub(crate) speculative_auth_mechanisms: Option<HashSet<AuthMechanism>>,
+
+    /// Fields to add to the body of each handshake command, outside of the `client` metadata.
+    #[serde(skip)]
+    pub(crate) handshake_extra_fields: Option<Document>,
+
+    /// The oldest TLS protocol version connections may use.
+    #[serde(skip)]
+    pub(crate) min_tls_version: Option<ProtocolVersion>,
+
+    /// Transforms the application name before it is sent in the handshake metadata.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[serde(skip)]
+    pub(crate) app_name_sanitizer: Option<AppNameSanitizer>,
 }
 
 impl ConnectionPoolOptions {
@@ -97,11 +228,14 @@ impl ConnectionPoolOptions {
             app_name: options.app_name.clone(),
             connect_timeout: options.connect_timeout,
             driver_info: options.driver_info.clone(),
+            driver_version: options.driver_version.clone(),
             max_idle_time: options.max_idle_time,
             min_pool_size: options.min_pool_size,
             max_pool_size: options.max_pool_size,
+            max_connecting: options.max_connecting,
             server_api: options.server_api.clone(),
             tls_options: options.tls_options(),
+            tls_sni_override: options.tls_sni_override.clone(),
             credential: options.credential.clone(),
             event_handler: options.cmap_event_handler.clone(),
             #[cfg(test)]
@@ -109,6 +243,28 @@ impl ConnectionPoolOptions {
             #[cfg(test)]
             ready: None,
             load_balanced: options.load_balanced,
+            write_concern: options.write_concern.clone(),
+            strict_write_concern: options.strict_write_concern,
+            min_server_wire_version: options.min_server_wire_version,
+            fips_mode: options.fips_mode,
+            handshake_timeout: options.handshake_timeout,
+            auth_timeout: options.auth_timeout,
+            include_hostname: options.include_hostname,
+            framework_version: options.framework_version.clone(),
+            omit_platform: options.omit_platform,
+            circuit_breaker: options.circuit_breaker.clone(),
+            on_handshake_complete: options.on_handshake_complete.clone(),
+            handshake_request_id: options.handshake_request_id,
+            handshake_reply_cache: options.handshake_reply_cache.clone(),
+            handshake_metadata_override: options.handshake_metadata_override.clone(),
+            handshake_target_db: options.handshake_target_db.clone(),
+            cert_matches_set: options.cert_matches_set.clone(),
+            validate_driver_name: options.validate_driver_name,
+            metadata_encoder: options.metadata_encoder.clone(),
+            speculative_auth_mechanisms: options.speculative_auth_mechanisms.clone(),
+            handshake_extra_fields: options.handshake_extra_fields.clone(),
+            min_tls_version: options.min_tls_version,
+            app_name_sanitizer: options.app_name_sanitizer.clone(),
         }
     }
 
@@ -154,6 +310,8 @@ pub(crate) struct ConnectionOptions {
 
     pub(crate) tls_options: Option<TlsOptions>,
 
+    pub(crate) tls_sni_override: Option<String>,
+
     #[derivative(Debug = "ignore")]
     pub(crate) event_handler: Option<Arc<dyn CmapEventHandler>>,
 }
@@ -163,6 +321,7 @@ impl From<ConnectionPoolOptions> for ConnectionOptions {
         Self {
             connect_timeout: pool_options.connect_timeout,
             tls_options: pool_options.tls_options,
+            tls_sni_override: pool_options.tls_sni_override,
             event_handler: pool_options.event_handler,
         }
     }
@@ -178,4 +337,8 @@ pub(crate) struct StreamOptions {
 
     #[builder(default)]
     pub(crate) tls_options: Option<TlsOptions>,
+
+    /// The hostname to present via SNI instead of the address's host, if any.
+    #[builder(default)]
+    pub(crate) tls_sni_override: Option<String>,
 }
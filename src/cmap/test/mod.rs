@@ -15,7 +15,7 @@ use crate::{
     cmap::{Connection, ConnectionPool, ConnectionPoolOptions},
     error::{Error, ErrorKind, Result},
     event::cmap::ConnectionPoolOptions as EventOptions,
-    options::TlsOptions,
+    options::{ServerAddress, TlsOptions},
     runtime::AsyncJoinHandle,
     sdam::{ServerUpdate, ServerUpdateSender},
     test::{
@@ -440,3 +440,33 @@ async fn cmap_spec_tests() {
 
     run_spec_test(&["connection-monitoring-and-pooling"], run_cmap_spec_tests).await;
 }
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn stale_generation_connection_rejected() {
+    let address = ServerAddress::parse("localhost:27017").unwrap();
+    let pool = ConnectionPool::new(
+        address.clone(),
+        Default::default(),
+        ServerUpdateSender::channel().0,
+        None,
+    );
+
+    let (stream, _remote) = tokio::io::duplex(64);
+    let connection = Connection::with_stream(1, address, 0, stream, None);
+    pool.check_generation(&connection).unwrap();
+
+    pool.clear(
+        ErrorKind::Internal {
+            message: "test".to_string(),
+        }
+        .into(),
+        None,
+    )
+    .await;
+
+    let error = pool.check_generation(&connection).unwrap_err();
+    assert!(error.is_pool_cleared());
+    // Whether the error is retryable is left to the executor's usual labeling.
+    assert!(error.labels().is_empty());
+}
@@ -39,12 +39,12 @@ use crate::{
 
 use std::{
     collections::{HashMap, VecDeque},
-    sync::Arc,
+    sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
 use tokio::sync::mpsc;
 
-const MAX_CONNECTING: u32 = 2;
+const DEFAULT_MAX_CONNECTING: u32 = 2;
 const MAINTENACE_FREQUENCY: Duration = Duration::from_millis(500);
 
 /// A worker task that manages the shared state of the pool.
@@ -112,6 +112,9 @@ pub(crate) struct ConnectionPoolWorker {
     /// wait_queue_timeout is exceeded.
     max_pool_size: u32,
 
+    /// The maximum number of connections that the pool can be establishing at a given time.
+    max_connecting: u32,
+
     /// Receiver used to determine if any threads hold references to this pool. If all the
     /// sender ends of this receiver drop, this worker will be notified and drop too.
     handle_listener: HandleListener,
@@ -146,8 +149,9 @@ impl ConnectionPoolWorker {
         http_client: HttpClient,
         server_updater: ServerUpdateSender,
         options: Option<ConnectionPoolOptions>,
+        draining: Arc<AtomicBool>,
     ) -> (PoolManager, ConnectionRequester, PoolGenerationSubscriber) {
-        let establisher = ConnectionEstablisher::new(http_client, options.as_ref());
+        let establisher = ConnectionEstablisher::new(http_client, options.as_ref(), draining);
         let event_handler = options.as_ref().and_then(|opts| opts.event_handler.clone());
 
         // The CMAP spec indicates that a max idle time of zero means that connections should not be
@@ -162,6 +166,11 @@ impl ConnectionPoolWorker {
             .and_then(|opts| opts.max_pool_size)
             .unwrap_or(DEFAULT_MAX_POOL_SIZE);
 
+        let max_connecting = options
+            .as_ref()
+            .and_then(|opts| opts.max_connecting)
+            .unwrap_or(DEFAULT_MAX_CONNECTING);
+
         let min_pool_size = options.as_ref().and_then(|opts| opts.min_pool_size);
 
         let connection_options: Option<ConnectionOptions> = options
@@ -229,6 +238,7 @@ impl ConnectionPoolWorker {
             connection_options,
             available_connections: VecDeque::new(),
             max_pool_size,
+            max_connecting,
             request_receiver,
             wait_queue: Default::default(),
             management_receiver,
@@ -344,7 +354,7 @@ impl ConnectionPoolWorker {
         }
 
         self.total_connection_count < self.max_pool_size
-            && self.pending_connection_count < MAX_CONNECTING
+            && self.pending_connection_count < self.max_connecting
     }
 
     async fn check_out(&mut self, request: ConnectionRequest) {
@@ -595,7 +605,7 @@ impl ConnectionPoolWorker {
     fn ensure_min_connections(&mut self) {
         if let Some(min_pool_size) = self.min_pool_size {
             while self.total_connection_count < min_pool_size
-                && self.pending_connection_count < MAX_CONNECTING
+                && self.pending_connection_count < self.max_connecting
             {
                 let pending_connection = self.create_pending_connection();
                 let event_handler = self.event_handler.clone();
@@ -1,8 +1,14 @@
 pub mod options;
 
-use std::{borrow::Borrow, collections::HashSet, fmt, fmt::Debug, sync::Arc};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    fmt,
+    fmt::Debug,
+    sync::Arc,
+};
 
-use futures_util::stream::StreamExt;
+use futures_util::stream::{StreamExt, TryStreamExt};
 use serde::{
     de::{DeserializeOwned, Error as DeError},
     Deserialize,
@@ -17,6 +23,8 @@ use crate::{
     client::session::TransactionState,
     concern::{ReadConcern, WriteConcern},
     error::{convert_bulk_errors, BulkWriteError, BulkWriteFailure, Error, ErrorKind, Result},
+    index::{index_diff, index_map},
+    options::ServerAddress,
     operation::{
         Aggregate,
         Count,
@@ -27,6 +35,7 @@ use crate::{
         Find,
         FindAndModify,
         Insert,
+        ListIndexes,
         Update,
     },
     results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult},
@@ -35,6 +44,7 @@ use crate::{
     ClientSession,
     Cursor,
     Database,
+    IndexModel,
     SessionCursor,
 };
 
@@ -197,6 +207,55 @@ impl<T> Collection<T> {
         self.drop_common(options, session).await
     }
 
+    /// Lists all indexes on this collection.
+    pub async fn list_indexes(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+    ) -> Result<Cursor<IndexModel>> {
+        let list_indexes = ListIndexes::new(self.namespace(), options.into());
+        let client = self.client();
+
+        client
+            .execute_cursor_operation(list_indexes)
+            .await
+            .map(|(spec, session)| Cursor::new(client.clone(), spec, session))
+    }
+
+    /// Lists all indexes on this collection, keyed by name. Indexes without a `name` are keyed by
+    /// the name the server gives an index created without one.
+    pub async fn list_indexes_by_name(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+    ) -> Result<HashMap<String, IndexModel>> {
+        let indexes: Vec<IndexModel> = self.list_indexes(options).await?.try_collect().await?;
+        index_map(indexes)
+    }
+
+    /// Compares the indexes on this collection with `desired` and returns the commands that would
+    /// reconcile them, without running any of them. The commands can be reviewed and then run in
+    /// order with [`Database::run_command`](struct.Database.html#method.run_command). See
+    /// [`index_diff`](fn.index_diff.html) for how the indexes are compared and
+    /// [`IndexDiff::commands`](struct.IndexDiff.html#method.commands) for the commands returned.
+    pub async fn plan_index_changes(&self, desired: &[IndexModel]) -> Result<Vec<Document>> {
+        let current: Vec<IndexModel> = self.list_indexes(None).await?.try_collect().await?;
+        index_diff(&current, desired).commands(self.name())
+    }
+
+    /// Lists all indexes on this collection using the provided `ClientSession`.
+    pub async fn list_indexes_with_session(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+        session: &mut ClientSession,
+    ) -> Result<SessionCursor<IndexModel>> {
+        let list_indexes = ListIndexes::new(self.namespace(), options.into());
+        let client = self.client();
+
+        client
+            .execute_operation(list_indexes, session)
+            .await
+            .map(|spec| SessionCursor::new(client.clone(), spec))
+    }
+
     /// Runs an aggregation operation.
     ///
     /// See the documentation [here](https://docs.mongodb.com/manual/aggregation/) for more
@@ -541,8 +600,9 @@ impl<T> Collection<T> {
             .await
     }
 
-    /// Kill the server side cursor that id corresponds to.
-    pub(super) async fn kill_cursor(&self, cursor_id: i64) -> Result<()> {
+    /// Kill the server side cursor that id corresponds to on the server at `address`, which must be
+    /// the server the cursor was created on.
+    pub(super) async fn kill_cursor(&self, cursor_id: i64, address: ServerAddress) -> Result<()> {
         let ns = self.namespace();
 
         self.client()
@@ -552,7 +612,7 @@ impl<T> Collection<T> {
                     "killCursors": ns.coll.as_str(),
                     "cursors": [cursor_id]
                 },
-                None,
+                SelectionCriteria::from_address(address),
             )
             .await?;
         Ok(())
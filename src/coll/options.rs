@@ -8,6 +8,7 @@ use crate::{
     bson::{doc, Bson, Document},
     bson_util,
     concern::{ReadConcern, WriteConcern},
+    index::{options::IndexOptions, IndexModel},
     options::Collation,
     selection_criteria::SelectionCriteria,
 };
@@ -842,3 +843,107 @@ pub struct DropCollectionOptions {
     /// The write concern for the operation.
     pub write_concern: Option<WriteConcern>,
 }
+
+/// Specifies the options to a
+/// [`Collection::list_indexes`](../struct.Collection.html#method.list_indexes) operation.
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, TypedBuilder, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[builder(field_defaults(default, setter(into)))]
+#[non_exhaustive]
+pub struct ListIndexesOptions {
+    /// The maximum amount of time to allow the query to run.
+    ///
+    /// This options maps to the `maxTimeMS` MongoDB query option, so the duration will be sent
+    /// across the wire as an integer number of milliseconds.
+    #[serde(
+        default,
+        serialize_with = "bson_util::serialize_duration_option_as_int_millis",
+        rename = "maxTimeMS",
+        deserialize_with = "bson_util::deserialize_duration_option_from_u64_millis"
+    )]
+    pub max_time: Option<Duration>,
+
+    /// The number of indexes the server should return per cursor batch.
+    ///
+    /// Note that this does not have any affect on the indexes that are returned by a cursor,
+    /// only the number of indexes kept in memory at a given time (and by extension, the
+    /// number of round trips needed to return the entire set of indexes).
+    #[serde(
+        serialize_with = "bson_util::serialize_u32_option_as_batch_size",
+        rename(serialize = "cursor")
+    )]
+    pub batch_size: Option<u32>,
+
+    /// The batch size the returned cursor may grow to. If this and `batch_size` are both set, the
+    /// batch size of the cursor's `getMore`s doubles after each full batch, up to this value,
+    /// which saves round trips for collections with many more indexes than fit in a batch.
+    ///
+    /// By default, every batch uses `batch_size`.
+    #[serde(skip_serializing)]
+    pub max_batch_size: Option<u32>,
+
+    /// The criteria used to select a server for this operation. Any tag sets on the read
+    /// preference are sent to the server in the order given.
+    ///
+    /// If none is specified, the primary will be used.
+    #[serde(skip_serializing)]
+    pub selection_criteria: Option<SelectionCriteria>,
+
+    /// The name of a shard to list the indexes of, rather than the indexes known to the mongos the
+    /// operation is routed to. This is useful for diagnosing indexes that are inconsistent across
+    /// the shards of a collection.
+    ///
+    /// This is an advanced option that relies on the server's internal `$_passthroughToShard`
+    /// parameter, so it should only be used for diagnostics. It requires a sharded cluster running
+    /// MongoDB 4.4 or newer.
+    #[serde(skip_serializing)]
+    pub shard: Option<String>,
+
+    /// Whether the returned cursor should request its next batch of indexes in the background as
+    /// soon as it starts returning the current one, rather than waiting until the current batch
+    /// has been consumed. This hides the latency of the `getMore`s for collections with more
+    /// indexes than fit in a batch, at the cost of holding up to two batches in memory.
+    ///
+    /// Prefetching is not done for cursors created with an explicit session. By default, batches
+    /// are not prefetched.
+    #[serde(skip_serializing)]
+    pub prefetch: Option<bool>,
+
+    /// The fields of each returned [`IndexModel`] to populate. Fields not requested by the mask
+    /// are left empty, which keeps less of each index in memory when only e.g. the names of a
+    /// collection's indexes are needed.
+    ///
+    /// The mask is applied by the driver as each batch of indexes is received, so it does not
+    /// reduce the amount of data sent by the server. By default, every field is populated.
+    #[serde(skip)]
+    pub fields: Option<IndexFieldMask>,
+}
+
+/// Specifies which fields of the indexes returned by a
+/// [`Collection::list_indexes`](../struct.Collection.html#method.list_indexes) operation to
+/// populate. Fields that are not requested are left empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, TypedBuilder)]
+#[builder(field_defaults(default))]
+#[non_exhaustive]
+pub struct IndexFieldMask {
+    /// Whether to populate the name of each index, which is kept as the only one of its options.
+    pub name: bool,
+
+    /// Whether to populate the keys of each index.
+    pub keys: bool,
+}
+
+impl IndexFieldMask {
+    pub(crate) fn apply(self, index: IndexModel) -> IndexModel {
+        let name = if self.name {
+            index.options.and_then(|opts| opts.name)
+        } else {
+            None
+        };
+        IndexModel {
+            keys: if self.keys { index.keys } else { Document::new() },
+            options: name.map(|name| IndexOptions::builder().name(name).build()),
+        }
+    }
+}
@@ -167,6 +167,10 @@ impl Serialize for ReadConcernLevel {
     }
 }
 
+/// The wire version of MongoDB 3.4, the first version to apply a write concern to every command
+/// that writes.
+const WRITE_CONCERN_ON_COMMANDS_MIN_WIRE_VERSION: i32 = 5;
+
 /// Specifies the level of acknowledgement requested from the server for write operations.
 ///
 /// See the documentation [here](https://docs.mongodb.com/manual/reference/write-concern/) for more
@@ -290,6 +294,23 @@ impl WriteConcern {
         Ok(())
     }
 
+    /// The minimum wire version a server must support for this write concern to be honored by all
+    /// write commands, if any. Servers older than 3.4 (wire version 5) do not apply a write concern
+    /// to commands like `findAndModify`, so anything beyond a plain node count requires it.
+    pub(crate) fn min_wire_version(&self) -> Option<i32> {
+        let needs_command_support = matches!(
+            self.w,
+            Some(Acknowledgment::Majority) | Some(Acknowledgment::Custom(_))
+        ) || self.w_timeout.is_some()
+            || self.journal.is_some();
+
+        if needs_command_support {
+            Some(WRITE_CONCERN_ON_COMMANDS_MIN_WIRE_VERSION)
+        } else {
+            None
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn serialize_for_client_options<S>(
         write_concern: &Option<WriteConcern>,
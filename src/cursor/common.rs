@@ -1,6 +1,7 @@
 use std::{
     collections::VecDeque,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
@@ -10,6 +11,7 @@ use futures_core::{Future, Stream};
 use serde::de::DeserializeOwned;
 
 use crate::{
+    bson::Binary,
     error::{Error, ErrorKind, Result},
     operation,
     options::ServerAddress,
@@ -31,6 +33,8 @@ where
     info: CursorInformation,
     buffer: VecDeque<T>,
     exhausted: bool,
+    #[derivative(Debug = "ignore")]
+    batch_transform: Option<BatchTransform<T>>,
 }
 
 impl<P, T> GenericCursor<P, T>
@@ -40,12 +44,15 @@ where
 {
     pub(super) fn new(client: Client, spec: CursorSpecification<T>, get_more_provider: P) -> Self {
         let exhausted = spec.id() == 0;
+        let mut info = spec.info;
+        info.grow_batch_size(spec.initial_buffer.len());
         Self {
             exhausted,
             client,
             provider: get_more_provider,
             buffer: spec.initial_buffer,
-            info: spec.info,
+            info,
+            batch_transform: spec.batch_transform,
         }
     }
 
@@ -65,6 +72,14 @@ where
         &self.info.ns
     }
 
+    pub(super) fn address(&self) -> &ServerAddress {
+        &self.info.address
+    }
+
+    pub(super) fn collection_uuid(&self) -> Option<&Binary> {
+        self.info.collection_uuid.as_ref()
+    }
+
     fn start_get_more(&mut self) {
         let info = self.info.clone();
         let client = self.client.clone();
@@ -81,25 +96,35 @@ where
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         loop {
-            // If there is a get more in flight, check on its status.
-            if let Some(future) = self.provider.executing_future() {
-                match Pin::new(future).poll(cx) {
-                    // If a result is ready, retrieve the buffer and update the exhausted status.
-                    Poll::Ready(get_more_result) => {
-                        let exhausted = get_more_result.exhausted();
-                        let (result, session) = get_more_result.into_parts();
-
-                        self.exhausted = exhausted;
-                        self.provider.clear_execution(session, exhausted);
-                        self.buffer = result?.batch;
+            // If there is a get more in flight, check on its status. A prefetched batch is only
+            // needed once the current one has been consumed.
+            if self.buffer.is_empty() {
+                if let Some(future) = self.provider.executing_future() {
+                    match Pin::new(future).poll(cx) {
+                        // If a result is ready, retrieve the buffer and update the exhausted
+                        // status.
+                        Poll::Ready(get_more_result) => {
+                            let exhausted = get_more_result.exhausted();
+                            let (result, session) = get_more_result.into_parts();
+
+                            self.exhausted = exhausted;
+                            self.provider.clear_execution(session, exhausted);
+                            self.buffer = result?.batch;
+                            if let Some(transform) = self.batch_transform.clone() {
+                                let batch = std::mem::take(&mut self.buffer);
+                                self.buffer = batch.into_iter().map(|doc| transform(doc)).collect();
+                            }
+                            let batch_len = self.buffer.len();
+                            self.info.grow_batch_size(batch_len);
+                        }
+                        Poll::Pending => return Poll::Pending,
                     }
-                    Poll::Pending => return Poll::Pending,
                 }
             }
 
             match self.buffer.pop_front() {
                 Some(doc) => {
-                    if self.buffer.is_empty() && !self.exhausted {
+                    if (self.buffer.is_empty() || self.info.prefetch) && !self.exhausted {
                         self.start_get_more();
                     }
                     return Poll::Ready(Some(Ok(doc)));
@@ -159,11 +184,17 @@ pub(super) trait GetMoreProviderResult {
     }
 }
 
+/// A function applied to each result of a cursor as its batch is received.
+pub(crate) type BatchTransform<T> = Arc<dyn Fn(T) -> T + Send + Sync>;
+
 /// Specification used to create a new cursor.
-#[derive(Debug, Clone)]
+#[derive(Derivative)]
+#[derivative(Debug, Clone(bound = "T: Clone"))]
 pub(crate) struct CursorSpecification<T> {
     pub(crate) info: CursorInformation,
     pub(crate) initial_buffer: VecDeque<T>,
+    #[derivative(Debug = "ignore")]
+    pub(crate) batch_transform: Option<BatchTransform<T>>,
 }
 
 impl<T> CursorSpecification<T> {
@@ -180,11 +211,40 @@ impl<T> CursorSpecification<T> {
                 address,
                 batch_size: batch_size.into(),
                 max_time: max_time.into(),
+                max_batch_size: None,
+                prefetch: false,
+                collection_uuid: info.collection_uuid,
             },
             initial_buffer: info.first_batch,
+            batch_transform: None,
         }
     }
 
+    /// Lets the cursor double its batch size after each full batch, up to `max_batch_size`, to
+    /// save round trips when there turn out to be many more results than a single batch holds.
+    /// This has no effect unless a batch size was specified.
+    pub(crate) fn with_batch_size_growth(mut self, max_batch_size: u32) -> Self {
+        self.info.max_batch_size = Some(max_batch_size);
+        self
+    }
+
+    /// Lets the cursor send the `getMore` for its next batch in the background as soon as it
+    /// starts returning the current batch, so the next batch is usually ready by the time the
+    /// current one has been consumed.
+    pub(crate) fn with_prefetch(mut self) -> Self {
+        self.info.prefetch = true;
+        self
+    }
+
+    /// Applies `transform` to each result of the cursor, both to those already buffered and to
+    /// those of every later batch as it is received.
+    pub(crate) fn with_batch_transform(mut self, transform: BatchTransform<T>) -> Self {
+        let initial_buffer = std::mem::take(&mut self.initial_buffer);
+        self.initial_buffer = initial_buffer.into_iter().map(|doc| transform(doc)).collect();
+        self.batch_transform = Some(transform);
+        self
+    }
+
     pub(crate) fn id(&self) -> i64 {
         self.info.id
     }
@@ -203,6 +263,11 @@ impl<T> CursorSpecification<T> {
     pub(crate) fn max_time(&self) -> Option<Duration> {
         self.info.max_time
     }
+
+    #[cfg(test)]
+    pub(crate) fn collection_uuid(&self) -> Option<&Binary> {
+        self.info.collection_uuid.as_ref()
+    }
 }
 
 /// Static information about a cursor.
@@ -213,4 +278,26 @@ pub(crate) struct CursorInformation {
     pub(crate) id: i64,
     pub(crate) batch_size: Option<u32>,
     pub(crate) max_time: Option<Duration>,
+
+    /// The size the batch size may grow to after full batches, if it may grow at all.
+    pub(crate) max_batch_size: Option<u32>,
+
+    /// Whether the `getMore` for the next batch is sent in the background while the current batch
+    /// is being consumed.
+    pub(crate) prefetch: bool,
+
+    /// The UUID of the collection the cursor is over, if the server reported it.
+    pub(crate) collection_uuid: Option<Binary>,
+}
+
+impl CursorInformation {
+    /// Doubles the batch size, up to `max_batch_size`, if a batch of `batch_len` documents filled
+    /// it.
+    fn grow_batch_size(&mut self, batch_len: usize) {
+        if let (Some(batch_size), Some(max_batch_size)) = (self.batch_size, self.max_batch_size) {
+            if batch_len >= batch_size as usize {
+                self.batch_size = Some(batch_size.saturating_mul(2).min(max_batch_size));
+            }
+        }
+    }
 }
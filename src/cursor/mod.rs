@@ -1,21 +1,27 @@
 mod common;
 pub(crate) mod session;
+#[cfg(test)]
+mod test;
 
 use std::{
+    cmp::Ordering,
     pin::Pin,
     task::{Context, Poll},
+    time::Instant,
 };
 
 use futures_core::{future::BoxFuture, Stream};
+use futures_util::StreamExt;
 use serde::de::DeserializeOwned;
 
 use crate::{
-    bson::Document,
-    error::{Error, Result},
+    bson::{Binary, Document},
+    error::{Error, ErrorKind, Result},
     operation::GetMore,
-    results::GetMoreResult,
+    results::{DrainResult, GetMoreResult},
     Client,
     ClientSession,
+    IndexModel,
     RUNTIME,
 };
 pub(crate) use common::{CursorInformation, CursorSpecification};
@@ -105,6 +111,94 @@ where
             _phantom: Default::default(),
         }
     }
+
+    /// Retrieves all of the remaining results of this cursor, stopping once `deadline` passes even
+    /// if more batches remain to be fetched. Unlike `maxTimeMS`, which bounds each individual
+    /// `getMore`, the deadline bounds the time spent draining the cursor as a whole.
+    ///
+    /// If draining stops early, the results retrieved so far are returned alongside the error that
+    /// stopped it. Any remaining `getMore`s are abandoned, and the cursor will be killed on the
+    /// server when it is dropped.
+    pub async fn drain_until(&mut self, deadline: Instant) -> DrainResult<T> {
+        let mut results = Vec::new();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let next = match RUNTIME.timeout(remaining, self.next()).await {
+                Ok(next) => next,
+                Err(error) => {
+                    return DrainResult {
+                        results,
+                        error: Some(error),
+                    }
+                }
+            };
+
+            match next {
+                Some(Ok(result)) => results.push(result),
+                Some(Err(error)) => {
+                    return DrainResult {
+                        results,
+                        error: Some(error),
+                    }
+                }
+                None => {
+                    return DrainResult {
+                        results,
+                        error: None,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Retrieves all of the remaining results of this cursor and returns an iterator over them in
+    /// the order given by `compare`, e.g. sorting the indexes from
+    /// [`Collection::list_indexes`](../struct.Collection.html#method.list_indexes) by name.
+    ///
+    /// Because every result must be retrieved before the first can be yielded, this buffers the
+    /// entire result set in memory rather than streaming it.
+    pub async fn sorted_by<F>(mut self, compare: F) -> Result<std::vec::IntoIter<T>>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut results = Vec::new();
+        while let Some(result) = self.next().await {
+            results.push(result?);
+        }
+
+        results.sort_by(compare);
+        Ok(results.into_iter())
+    }
+}
+
+impl Cursor<IndexModel> {
+    /// The UUID of the collection whose indexes this cursor lists, if the server reported it. Since
+    /// a dropped and recreated collection gets a new UUID, this can be used to detect that the
+    /// collection was recreated between two listings of its indexes.
+    pub fn collection_uuid(&self) -> Option<&Binary> {
+        self.wrapped_cursor.collection_uuid()
+    }
+
+    /// Retrieves all of the remaining indexes from this cursor, e.g. one returned by
+    /// [`Collection::list_indexes`](../struct.Collection.html#method.list_indexes), and partitions
+    /// them into the visible indexes and the [hidden
+    /// indexes](https://docs.mongodb.com/manual/core/index-hidden/), in that order. Indexes without
+    /// a `hidden` flag are considered visible.
+    pub async fn partition_by_visibility(mut self) -> Result<(Vec<IndexModel>, Vec<IndexModel>)> {
+        let mut visible = Vec::new();
+        let mut hidden = Vec::new();
+        while let Some(index) = self.next().await {
+            let index = index?;
+            if index.is_hidden() {
+                hidden.push(index);
+            } else {
+                visible.push(index);
+            }
+        }
+
+        Ok((visible, hidden))
+    }
 }
 
 impl<T> Stream for Cursor<T>
@@ -133,7 +227,8 @@ where
             .database(ns.db.as_str())
             .collection::<Document>(ns.coll.as_str());
         let cursor_id = self.wrapped_cursor.id();
-        RUNTIME.execute(async move { coll.kill_cursor(cursor_id).await });
+        let address = self.wrapped_cursor.address().clone();
+        RUNTIME.execute(async move { coll.kill_cursor(cursor_id, address).await });
     }
 }
 
@@ -159,6 +254,18 @@ impl<T> GetMoreProviderResult for ImplicitSessionGetMoreResult<T> {
     }
 }
 
+/// Runs a getMore for the cursor described by `info`.
+async fn run_get_more<T: DeserializeOwned>(
+    client: &Client,
+    info: CursorInformation,
+    session: &mut Option<Box<ClientSession>>,
+) -> Result<GetMoreResult<T>> {
+    let get_more = GetMore::new(info);
+    client
+        .execute_operation(get_more, session.as_mut().map(|b| b.as_mut()))
+        .await
+}
+
 /// A `GetMoreProvider` that optionally owns its own session.
 /// This is to be used with cursors associated with implicit sessions.
 enum ImplicitSessionGetMoreProvider<T> {
@@ -200,12 +307,47 @@ impl<T: Send + Sync + DeserializeOwned> GetMoreProvider for ImplicitSessionGetMo
 
     fn start_execution(&mut self, info: CursorInformation, client: Client) {
         take_mut::take(self, |self_| match self_ {
+            Self::Idle(mut session) if info.prefetch => {
+                // A prefetched getMore is run as a separate task so that it makes progress while
+                // the cursor isn't being polled. The task can't hold on to the document type, so
+                // the batch is deserialized once the cursor needs it.
+                let handle = RUNTIME.spawn(async move {
+                    let result = run_get_more::<Document>(&client, info, &mut session).await;
+                    (result, session)
+                });
+                Self::Executing(Box::pin(async move {
+                    let (result, session) = match handle {
+                        Some(handle) => handle.await,
+
+                        // The async runtime was dropped, so there is nothing left to run the
+                        // getMore.
+                        None => (
+                            Err(ErrorKind::Internal {
+                                message: "the async runtime was shut down".to_string(),
+                            }
+                            .into()),
+                            None,
+                        ),
+                    };
+                    ImplicitSessionGetMoreResult {
+                        get_more_result: result.and_then(|result| {
+                            let batch = result
+                                .batch
+                                .into_iter()
+                                .map(|doc| bson::from_document(doc).map_err(Error::from))
+                                .collect::<Result<_>>()?;
+                            Ok(GetMoreResult {
+                                batch,
+                                exhausted: result.exhausted,
+                            })
+                        }),
+                        session,
+                    }
+                }))
+            }
             Self::Idle(mut session) => {
                 let future = Box::pin(async move {
-                    let get_more = GetMore::new(info);
-                    let get_more_result = client
-                        .execute_operation(get_more, session.as_mut().map(|b| b.as_mut()))
-                        .await;
+                    let get_more_result = run_get_more(&client, info, &mut session).await;
                     ImplicitSessionGetMoreResult {
                         get_more_result,
                         session,
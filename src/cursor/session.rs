@@ -4,11 +4,18 @@ use std::{
     task::{Context, Poll},
 };
 
+use derivative::Derivative;
 use futures_core::{future::BoxFuture, Stream};
 use futures_util::StreamExt;
 use serde::de::DeserializeOwned;
 
-use super::common::{CursorInformation, GenericCursor, GetMoreProvider, GetMoreProviderResult};
+use super::common::{
+    BatchTransform,
+    CursorInformation,
+    GenericCursor,
+    GetMoreProvider,
+    GetMoreProviderResult,
+};
 use crate::{
     bson::Document,
     cursor::CursorSpecification,
@@ -47,7 +54,8 @@ use crate::{
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
+#[derive(Derivative)]
+#[derivative(Debug(bound = "T: std::fmt::Debug"))]
 pub struct SessionCursor<T>
 where
     T: DeserializeOwned + Unpin,
@@ -56,6 +64,8 @@ where
     client: Client,
     info: CursorInformation,
     buffer: VecDeque<T>,
+    #[derivative(Debug = "ignore")]
+    batch_transform: Option<BatchTransform<T>>,
 }
 
 impl<T> SessionCursor<T>
@@ -65,11 +75,17 @@ where
     pub(crate) fn new(client: Client, spec: CursorSpecification<T>) -> Self {
         let exhausted = spec.id() == 0;
 
+        // The session is only borrowed while the cursor is being iterated, so a getMore can't be
+        // left running in the background.
+        let mut info = spec.info;
+        info.prefetch = false;
+
         Self {
             exhausted,
             client,
-            info: spec.info,
+            info,
             buffer: spec.initial_buffer,
+            batch_transform: spec.batch_transform,
         }
     }
 
@@ -126,6 +142,7 @@ where
         let spec = CursorSpecification {
             info: self.info.clone(),
             initial_buffer: std::mem::take(&mut self.buffer),
+            batch_transform: self.batch_transform.clone(),
         };
         SessionCursorStream {
             generic_cursor: ExplicitSessionCursor::new(
@@ -180,7 +197,8 @@ where
             .database(ns.db.as_str())
             .collection::<Document>(ns.coll.as_str());
         let cursor_id = self.info.id;
-        RUNTIME.execute(async move { coll.kill_cursor(cursor_id).await });
+        let address = self.info.address.clone();
+        RUNTIME.execute(async move { coll.kill_cursor(cursor_id, address).await });
     }
 }
 
@@ -0,0 +1,463 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures_util::StreamExt;
+
+use crate::{
+    bson::{doc, Document},
+    cursor::{Cursor, CursorSpecification},
+    error::ErrorKind,
+    operation::CursorInfo,
+    options::{ClientOptions, IndexOptions, ServerAddress, ServerApi, ServerApiVersion},
+    test::start_fake_server,
+    Client,
+    IndexModel,
+    Namespace,
+};
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn drain_until_deadline_returns_partial_results() {
+    // Nothing is listening on this address, so the getMore for the second batch will wait on
+    // server selection until well after the deadline.
+    let address = ServerAddress::parse("localhost:1").unwrap();
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(10))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let first_batch: Vec<Document> = vec![doc! { "v": 2, "key": { "_id": 1 }, "name": "_id_" }];
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: first_batch.clone().into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let spec = CursorSpecification::new(info, address, None, None);
+    let mut cursor = Cursor::new(client, spec, None);
+
+    let start = Instant::now();
+    let drained = cursor
+        .drain_until(Instant::now() + Duration::from_millis(200))
+        .await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    assert_eq!(drained.results, first_batch);
+    match drained.error.as_ref().map(|error| error.kind.as_ref()) {
+        Some(ErrorKind::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::TimedOut),
+        other => panic!("expected timeout error, got {:?}", other),
+    }
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn sorted_by_index_name() {
+    let address = ServerAddress::parse("localhost:1").unwrap();
+    let client = Client::with_options(
+        ClientOptions::builder()
+            .hosts(vec![address.clone()])
+            .build(),
+    )
+    .unwrap();
+
+    let index = |name: &str| {
+        IndexModel::builder()
+            .keys(doc! { name: 1 })
+            .options(IndexOptions::builder().name(format!("{}_1", name)).build())
+            .build()
+    };
+    let info = CursorInfo {
+        id: 0,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![index("c"), index("a"), index("b")].into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let cursor = Cursor::new(client, CursorSpecification::new(info, address, None, None), None);
+
+    let names: Vec<String> = cursor
+        .sorted_by(|a, b| {
+            let name = |index: &IndexModel| index.options.as_ref().and_then(|o| o.name.clone());
+            name(a).cmp(&name(b))
+        })
+        .await
+        .unwrap()
+        .map(|index| index.options.unwrap().name.unwrap())
+        .collect();
+    assert_eq!(names, vec!["a_1", "b_1", "c_1"]);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn partition_by_visibility() {
+    let address = ServerAddress::parse("localhost:1").unwrap();
+    let client = Client::with_options(
+        ClientOptions::builder()
+            .hosts(vec![address.clone()])
+            .build(),
+    )
+    .unwrap();
+
+    let index = |name: &str, hidden: Option<bool>| {
+        IndexModel::builder()
+            .keys(doc! { name: 1 })
+            .options(
+                IndexOptions::builder()
+                    .name(format!("{}_1", name))
+                    .hidden(hidden)
+                    .build(),
+            )
+            .build()
+    };
+    let info = CursorInfo {
+        id: 0,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![
+            IndexModel::builder().keys(doc! { "_id": 1 }).build(),
+            index("a", Some(true)),
+            index("b", None),
+            index("c", Some(false)),
+            index("d", Some(true)),
+        ]
+        .into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let cursor = Cursor::new(client, CursorSpecification::new(info, address, None, None), None);
+
+    let (visible, hidden) = cursor.partition_by_visibility().await.unwrap();
+    let keys = |indexes: Vec<IndexModel>| -> Vec<Document> {
+        indexes.into_iter().map(|index| index.keys).collect()
+    };
+    assert_eq!(
+        keys(visible),
+        vec![doc! { "_id": 1 }, doc! { "b": 1 }, doc! { "c": 1 }]
+    );
+    assert_eq!(keys(hidden), vec![doc! { "a": 1 }, doc! { "d": 1 }]);
+}
+
+/// Replies to a `getMore` with an exhausted cursor.
+fn exhausted_get_more_reply(_get_more: &Document) -> Option<Document> {
+    Some(doc! {
+        "ok": 1.0,
+        "cursor": { "id": 0_i64, "ns": "db.coll", "nextBatch": [] },
+    })
+}
+
+/// Starts a fake standalone server that answers handshakes and heartbeats and replies to every
+/// `getMore` with `get_more_reply`, or closes the connection if it returns `None`. Each `getMore`
+/// command received is sent on `get_mores`, as is each `killCursors` command, which always
+/// succeeds.
+fn start_cursor_server(
+    get_mores: mpsc::Sender<Document>,
+    get_more_reply: fn(&Document) -> Option<Document>,
+) -> ServerAddress {
+    let get_mores = Mutex::new(get_mores);
+    start_fake_server(move |command| {
+        if command.contains_key("getMore") {
+            let reply = get_more_reply(command);
+            let _ = get_mores.lock().unwrap().send(command.clone());
+            reply
+        } else if command.contains_key("killCursors") {
+            let _ = get_mores.lock().unwrap().send(command.clone());
+            Some(doc! { "ok": 1.0 })
+        } else {
+            Some(doc! {
+                "ok": 1.0,
+                "ismaster": true,
+                "maxBsonObjectSize": 16777216,
+                "maxWriteBatchSize": 100000,
+                "maxWireVersion": 13,
+                "minWireVersion": 0,
+                "logicalSessionTimeoutMinutes": 30,
+            })
+        }
+    })
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn get_more_includes_server_api() {
+    let (sender, get_mores) = mpsc::channel();
+    let address = start_cursor_server(sender, exhausted_get_more_reply);
+
+    let server_api = ServerApi::builder()
+        .version(ServerApiVersion::V1)
+        .strict(true)
+        .build();
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_api(server_api)
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: Default::default(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let mut cursor: Cursor<Document> =
+        Cursor::new(client, CursorSpecification::new(info, address, None, None), None);
+    assert!(cursor.next().await.is_none());
+
+    let get_more = get_mores.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(get_more.get_i64("getMore"), Ok(123));
+    assert_eq!(get_more.get_str("apiVersion"), Ok("1"));
+    assert_eq!(get_more.get_bool("apiStrict"), Ok(true));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn batch_size_grows_after_full_batches() {
+    // Fills the batches of the first three `getMore`s, then exhausts the cursor.
+    fn get_more_reply(get_more: &Document) -> Option<Document> {
+        static GET_MORES: AtomicUsize = AtomicUsize::new(0);
+
+        let batch_size = get_more.get_i32("batchSize").unwrap();
+        let (id, batch) = if GET_MORES.fetch_add(1, Ordering::SeqCst) < 3 {
+            (123_i64, vec![doc! {}; batch_size as usize])
+        } else {
+            (0, Vec::new())
+        };
+        Some(doc! {
+            "ok": 1.0,
+            "cursor": { "id": id, "ns": "db.coll", "nextBatch": batch },
+        })
+    }
+
+    let (sender, get_mores) = mpsc::channel();
+    let address = start_cursor_server(sender, get_more_reply);
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![doc! {}; 2].into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let spec = CursorSpecification::new(info, address, 2, None).with_batch_size_growth(6);
+    let cursor: Cursor<Document> = Cursor::new(client, spec, None);
+    let results: Vec<_> = cursor.collect().await;
+    assert_eq!(results.len(), 2 + 4 + 6 + 6);
+
+    // The batch size doubles after each full batch, up to the maximum.
+    let batch_sizes: Vec<_> = get_mores
+        .try_iter()
+        .map(|get_more| get_more.get_i32("batchSize").unwrap())
+        .collect();
+    assert_eq!(batch_sizes, vec![4, 6, 6, 6]);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn get_more_not_retried_after_network_error() {
+    // Drops the connection of the first `getMore`. The server may have run it before the
+    // connection was lost, so re-sending it could silently skip a batch.
+    fn get_more_reply(_get_more: &Document) -> Option<Document> {
+        None
+    }
+
+    let (sender, get_mores) = mpsc::channel();
+    let address = start_cursor_server(sender, get_more_reply);
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .heartbeat_freq(Duration::from_millis(500))
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![doc! { "x": 1 }].into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let spec = CursorSpecification::new(info, address, None, None);
+    let mut cursor: Cursor<Document> = Cursor::new(client, spec, None);
+    assert_eq!(cursor.next().await.unwrap().unwrap(), doc! { "x": 1 });
+    let error = cursor.next().await.unwrap().unwrap_err();
+    assert!(error.is_network_error(), "{:?}", error);
+    drop(cursor);
+
+    let get_more_count = get_mores
+        .try_iter()
+        .filter(|command| command.contains_key("getMore"))
+        .count();
+    assert_eq!(get_more_count, 1);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn kill_cursors_sent_to_originating_server_on_drop() {
+    let (sender, commands) = mpsc::channel();
+    let address = start_cursor_server(sender, exhausted_get_more_reply);
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![
+            IndexModel::builder().keys(doc! { "_id": 1 }).build(),
+            IndexModel::builder().keys(doc! { "a": 1 }).build(),
+        ]
+        .into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let mut cursor: Cursor<IndexModel> =
+        Cursor::new(client, CursorSpecification::new(info, address, None, None), None);
+    assert!(cursor.next().await.unwrap().is_ok());
+    drop(cursor);
+
+    let kill_cursors = commands.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(kill_cursors.get_str("killCursors"), Ok("coll"));
+    assert_eq!(kill_cursors.get_array("cursors"), Ok(&vec![123_i64.into()]));
+    assert_eq!(kill_cursors.get_str("$db"), Ok("db"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn get_more_prefetched_before_batch_consumed() {
+    fn get_more_reply(_get_more: &Document) -> Option<Document> {
+        Some(doc! {
+            "ok": 1.0,
+            "cursor": {
+                "id": 0_i64,
+                "ns": "db.coll",
+                "nextBatch": [{ "v": 2, "key": { "c": 1 }, "name": "c_1" }],
+            },
+        })
+    }
+
+    let (sender, get_mores) = mpsc::channel();
+    let address = start_cursor_server(sender, get_more_reply);
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![
+            IndexModel::builder().keys(doc! { "_id": 1 }).build(),
+            IndexModel::builder().keys(doc! { "a": 1 }).build(),
+            IndexModel::builder().keys(doc! { "b": 1 }).build(),
+        ]
+        .into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let spec = CursorSpecification::new(info, address, None, None).with_prefetch();
+    let mut cursor: Cursor<IndexModel> = Cursor::new(client, spec, None);
+
+    // The getMore is sent while the rest of the first batch is still buffered.
+    assert_eq!(cursor.next().await.unwrap().unwrap().keys, doc! { "_id": 1 });
+    let get_more = get_mores.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(get_more.get_i64("getMore"), Ok(123));
+
+    let keys: Vec<Document> = cursor.map(|index| index.unwrap().keys).collect().await;
+    assert_eq!(keys, vec![doc! { "a": 1 }, doc! { "b": 1 }, doc! { "c": 1 }]);
+    assert!(get_mores.try_recv().is_err());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn dropping_cursor_during_prefetch_returns_session() {
+    // Holds the reply back so that the cursor is dropped while the getMore is still in flight.
+    fn get_more_reply(_get_more: &Document) -> Option<Document> {
+        std::thread::sleep(Duration::from_millis(500));
+        Some(doc! {
+            "ok": 1.0,
+            "cursor": { "id": 123_i64, "ns": "db.coll", "nextBatch": [{ "v": 2 }] },
+        })
+    }
+
+    let (sender, get_mores) = mpsc::channel();
+    let address = start_cursor_server(sender, get_more_reply);
+    let options = ClientOptions::builder()
+        .hosts(vec![address.clone()])
+        .server_selection_timeout(Duration::from_secs(5))
+        .build();
+    let client = Client::with_options(options).unwrap();
+
+    let session = client
+        .start_session_with_timeout(Duration::from_secs(30 * 60), None, true)
+        .await;
+    let session_id = session.id().clone();
+
+    let info = CursorInfo {
+        id: 123,
+        ns: Namespace {
+            db: "db".to_string(),
+            coll: "coll".to_string(),
+        },
+        first_batch: vec![doc! { "v": 1 }, doc! { "v": 1 }].into(),
+        at_cluster_time: None,
+        collection_uuid: None,
+    };
+    let spec = CursorSpecification::new(info, address, None, None).with_prefetch();
+    let mut cursor: Cursor<Document> = Cursor::new(client.clone(), spec, Some(session));
+
+    assert!(cursor.next().await.unwrap().is_ok());
+    let get_more = get_mores.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(get_more.get_i64("getMore"), Ok(123));
+    drop(cursor);
+
+    // The prefetch task owns the session, so it is returned to the pool once the getMore
+    // completes even though nothing is waiting on its result anymore.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !client.is_session_checked_in(&session_id).await {
+        assert!(
+            Instant::now() < deadline,
+            "implicit session was not returned to the pool"
+        );
+        crate::RUNTIME.delay_for(Duration::from_millis(50)).await;
+    }
+}
@@ -20,8 +20,26 @@ const RETRYABLE_READ_CODES: [i32; 11] =
 const RETRYABLE_WRITE_CODES: [i32; 12] = [
     11600, 11602, 10107, 13435, 13436, 189, 91, 7, 6, 89, 9001, 262,
 ];
+const COMMAND_NOT_FOUND_CODE: i32 = 59;
 const UNKNOWN_TRANSACTION_COMMIT_RESULT_LABEL_CODES: [i32; 3] = [50, 64, 91];
 
+/// The OS error codes reported when no route to a host or its network exists, which the standard
+/// library doesn't map to an `io::ErrorKind`.
+#[cfg(target_os = "linux")]
+const UNREACHABLE_OS_ERRORS: [i32; 2] = [113, 101];
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+const UNREACHABLE_OS_ERRORS: [i32; 2] = [65, 51];
+#[cfg(windows)]
+const UNREACHABLE_OS_ERRORS: [i32; 2] = [10065, 10051];
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    windows
+)))]
+const UNREACHABLE_OS_ERRORS: [i32; 0] = [];
+
 /// Retryable write error label. This label will be added to an error when the error is
 /// write-retryable.
 pub const RETRYABLE_WRITE_ERROR: &str = "RetryableWriteError";
@@ -287,6 +305,27 @@ impl Error {
             .unwrap_or(false)
     }
 
+    /// Whether this error indicates that a connection to the host could not be opened because the
+    /// connection was refused or the host was unreachable (e.g. because it was removed from the
+    /// deployment).
+    pub(crate) fn is_connect_failure(&self) -> bool {
+        match self.kind.as_ref() {
+            ErrorKind::Io(ref io_err) => {
+                io_err.kind() == std::io::ErrorKind::ConnectionRefused
+                    || io_err
+                        .raw_os_error()
+                        .map(|code| UNREACHABLE_OS_ERRORS.contains(&code))
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether this error indicates that the server does not recognize the command that was sent.
+    pub(crate) fn is_command_not_found(&self) -> bool {
+        self.code() == Some(COMMAND_NOT_FOUND_CODE)
+    }
+
     /// If this error corresponds to a "node is shutting down" error as per the SDAM spec.
     pub(crate) fn is_shutting_down(&self) -> bool {
         self.code()
@@ -0,0 +1,107 @@
+//! Contains the events and functionality for monitoring the servers that a `Client` is connected
+//! to.
+
+use std::time::Duration;
+
+use crate::{bson::Document, error::Error, options::ServerAddress};
+
+/// Event emitted when a server check starts. This includes the check performed as part of the
+/// handshake on a monitoring connection, which is the first check of each server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ServerHeartbeatStartedEvent {
+    /// The address of the server being checked.
+    pub server_address: ServerAddress,
+
+    /// Whether the check uses the streaming protocol, waiting for the server to report a change
+    /// rather than replying immediately. The driver currently always polls, so this is always
+    /// false.
+    pub awaited: bool,
+}
+
+/// Event emitted when a server check succeeds. This includes the check performed as part of the
+/// handshake on a monitoring connection, which is the first check of each server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ServerHeartbeatSucceededEvent {
+    /// The execution time of the check.
+    pub duration: Duration,
+
+    /// The server's reply to the `isMaster` or `hello` command used for the check.
+    pub reply: Document,
+
+    /// The address of the server that was checked.
+    pub server_address: ServerAddress,
+
+    /// Whether the check used the streaming protocol. The driver currently always polls, so this
+    /// is always false.
+    pub awaited: bool,
+}
+
+/// Event emitted when a server check fails. This includes the check performed as part of the
+/// handshake on a monitoring connection, which is the first check of each server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ServerHeartbeatFailedEvent {
+    /// The execution time of the check.
+    pub duration: Duration,
+
+    /// The error that caused the check to fail.
+    pub failure: Error,
+
+    /// The address of the server that was checked.
+    pub server_address: ServerAddress,
+
+    /// Whether the check used the streaming protocol. The driver currently always polls, so this
+    /// is always false.
+    pub awaited: bool,
+}
+
+/// Applications can implement this trait to specify custom logic to run on each SDAM event sent
+/// by the driver.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// #
+/// # use mongodb::{
+/// #     error::Result,
+/// #     event::sdam::{SdamEventHandler, ServerHeartbeatSucceededEvent},
+/// #     options::ClientOptions,
+/// # };
+/// # #[cfg(feature = "sync")]
+/// # use mongodb::sync::Client;
+/// # #[cfg(not(feature = "sync"))]
+/// # use mongodb::Client;
+/// #
+/// struct HeartbeatLogger;
+///
+/// impl SdamEventHandler for HeartbeatLogger {
+///     fn handle_server_heartbeat_succeeded_event(&self, event: ServerHeartbeatSucceededEvent) {
+///         eprintln!("Checked {} in {:?}", event.server_address, event.duration);
+///     }
+/// }
+///
+/// # fn do_stuff() -> Result<()> {
+/// let handler: Arc<dyn SdamEventHandler> = Arc::new(HeartbeatLogger);
+/// let options = ClientOptions::builder()
+///                   .sdam_event_handler(handler)
+///                   .build();
+/// let client = Client::with_options(options)?;
+///
+/// // Do things with the client, and successful server checks will be logged to stderr.
+/// # Ok(())
+/// # }
+/// ```
+pub trait SdamEventHandler: Send + Sync {
+    /// A [`Client`](../../struct.Client.html) will call this method on each registered handler
+    /// whenever a server check starts.
+    fn handle_server_heartbeat_started_event(&self, _event: ServerHeartbeatStartedEvent) {}
+
+    /// A [`Client`](../../struct.Client.html) will call this method on each registered handler
+    /// whenever a server check succeeds.
+    fn handle_server_heartbeat_succeeded_event(&self, _event: ServerHeartbeatSucceededEvent) {}
+
+    /// A [`Client`](../../struct.Client.html) will call this method on each registered handler
+    /// whenever a server check fails.
+    fn handle_server_heartbeat_failed_event(&self, _event: ServerHeartbeatFailedEvent) {}
+}
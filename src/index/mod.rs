@@ -0,0 +1,524 @@
+pub mod options;
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use self::options::IndexOptions;
+use crate::{
+    bson::{self, doc, Bson, Document},
+    bson_util,
+    error::{ErrorKind, Result},
+};
+
+/// Specifies the fields and options for an index. For more information, see the
+/// [documentation](https://docs.mongodb.com/manual/indexes/).
+#[derive(Clone, Debug, Default, Deserialize, TypedBuilder, Serialize, PartialEq)]
+#[builder(field_defaults(default, setter(into)))]
+#[non_exhaustive]
+pub struct IndexModel {
+    /// Specifies the index's fields. For each field, specify a key-value pair in which the key is
+    /// the name of the field to index and the value is the index type.
+    #[serde(rename = "key")]
+    pub keys: Document,
+
+    /// The options for the index.
+    #[serde(flatten)]
+    pub options: Option<IndexOptions>,
+}
+
+impl IndexModel {
+    /// Whether this is a [partial index](https://docs.mongodb.com/manual/core/index-partial/),
+    /// i.e. whether it only indexes the documents matching a `partialFilterExpression`.
+    pub fn is_partial(&self) -> bool {
+        self.options
+            .as_ref()
+            .and_then(|options| options.partial_filter_expression.as_ref())
+            .is_some()
+    }
+
+    /// Whether this is a [hidden index](https://docs.mongodb.com/manual/core/index-hidden/), i.e.
+    /// whether it is hidden from the query planner. Indexes without a `hidden` flag are visible.
+    pub fn is_hidden(&self) -> bool {
+        self.options
+            .as_ref()
+            .and_then(|options| options.hidden)
+            .unwrap_or(false)
+    }
+
+    /// The name of the index: its `name` option, or the name the server gives an index created
+    /// without one.
+    pub fn resolved_name(&self) -> String {
+        match self.options.as_ref().and_then(|options| options.name.as_ref()) {
+            Some(name) => name.clone(),
+            None => default_index_name(&self.create_indexes_keys()),
+        }
+    }
+
+    /// The fields of the index's key pattern, in order, each with the direction or type it is
+    /// indexed by.
+    pub fn key_fields(&self) -> Vec<(String, IndexDirection)> {
+        self.keys
+            .iter()
+            .map(|(name, value)| (name.clone(), IndexDirection::from(value)))
+            .collect()
+    }
+
+    /// A one-line, human-readable description of the index for display, e.g.
+    /// `name_1_age_-1 (unique, partial) on {name: 1, age: -1}`. The notable options listed in
+    /// parentheses are `unique`, `sparse`, `partial`, `hidden` and the TTL of TTL indexes; the
+    /// parentheses are left out if none apply.
+    pub fn summary(&self) -> String {
+        let mut notable = Vec::new();
+        if let Some(ref options) = self.options {
+            if options.unique == Some(true) {
+                notable.push("unique".to_string());
+            }
+            if options.sparse == Some(true) {
+                notable.push("sparse".to_string());
+            }
+            if self.is_partial() {
+                notable.push("partial".to_string());
+            }
+            if self.is_hidden() {
+                notable.push("hidden".to_string());
+            }
+            if let Some(expire_after) = options.expire_after {
+                notable.push(format!("ttl {}s", expire_after.as_secs()));
+            }
+        }
+
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .map(|(field, value)| format!("{}: {}", field, value))
+            .collect();
+
+        let mut summary = self.resolved_name();
+        if !notable.is_empty() {
+            summary.push_str(&format!(" ({})", notable.join(", ")));
+        }
+        summary.push_str(&format!(" on {{{}}}", keys.join(", ")));
+        summary
+    }
+
+    /// Checks for combinations of keys and options that the server will reject when creating the
+    /// index, such as a unique wildcard index or a sparse partial index. This allows indexes read
+    /// from one deployment to be checked before they are re-created elsewhere.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| -> Result<()> {
+            Err(ErrorKind::InvalidArgument {
+                message: format!("invalid index {}: {}", self.keys, message),
+            }
+            .into())
+        };
+
+        if self.keys.is_empty() {
+            return invalid("the key pattern must not be empty");
+        }
+
+        let is_wildcard = self
+            .keys
+            .keys()
+            .any(|name| name == "$**" || name.ends_with(".$**"));
+        let is_hashed = self
+            .keys
+            .values()
+            .any(|value| value.as_str() == Some("hashed"));
+
+        let options = match self.options {
+            Some(ref options) => options,
+            None => return Ok(()),
+        };
+        let unique = options.unique == Some(true);
+
+        if unique && is_wildcard {
+            return invalid("wildcard indexes cannot be unique");
+        }
+        if unique && is_hashed {
+            return invalid("hashed indexes cannot be unique");
+        }
+        if options.sparse == Some(true) && options.partial_filter_expression.is_some() {
+            return invalid("an index cannot be both sparse and partial");
+        }
+        if options.wildcard_projection.is_some() && !self.keys.contains_key("$**") {
+            return invalid("a wildcard projection requires the key pattern { \"$**\": 1 }");
+        }
+
+        Ok(())
+    }
+
+    /// Builds the specification of this index in the exact shape the `createIndexes` command
+    /// expects, e.g. to re-create an index read from
+    /// [`Collection::list_indexes`](struct.Collection.html#method.list_indexes) on another
+    /// collection.
+    ///
+    /// The specification starts with the `key` pattern and the `name`, followed by the options.
+    /// Text indexes are reported by `listIndexes` with the internal `_fts` and `_ftsx` keys and
+    /// their fields in the `weights` option, so their key pattern is rebuilt from the weighted
+    /// fields. The `ns` field reported by older servers is dropped, and the server's default name
+    /// is generated for indexes without one.
+    pub fn to_create_indexes_spec(&self) -> Result<Document> {
+        let mut options = match self.options {
+            Some(ref options) => bson::to_document(options)?,
+            None => Document::new(),
+        };
+        options.remove("ns");
+
+        let keys = self.create_indexes_keys();
+        let name = match options.remove("name") {
+            Some(name) => name,
+            None => Bson::String(default_index_name(&keys)),
+        };
+
+        let mut spec = doc! { "key": keys, "name": name };
+        spec.extend(options);
+        Ok(spec)
+    }
+
+    /// The key pattern of this index as given to `createIndexes`, with the internal `_fts` and
+    /// `_ftsx` keys of a text index replaced by the weighted fields.
+    fn create_indexes_keys(&self) -> Document {
+        let weights = self
+            .options
+            .as_ref()
+            .and_then(|options| options.weights.as_ref());
+        let weights = match weights {
+            Some(weights) if self.keys.get_str("_fts") == Ok("text") => weights,
+            _ => return self.keys.clone(),
+        };
+
+        let mut keys = Document::new();
+        for (name, value) in self.keys.iter() {
+            match name.as_str() {
+                "_fts" => {
+                    for field in weights.keys() {
+                        keys.insert(field.clone(), "text");
+                    }
+                }
+                "_ftsx" => {}
+                _ => {
+                    keys.insert(name.clone(), value.clone());
+                }
+            }
+        }
+        keys
+    }
+
+    /// The options of this index that determine its behavior, i.e. without its name or the fields
+    /// the server reports for every index.
+    fn comparable_options(&self) -> Document {
+        let mut options = self
+            .options
+            .as_ref()
+            .and_then(|options| bson::to_document(options).ok())
+            .unwrap_or_default();
+        for field in &["name", "ns", "v"] {
+            options.remove(field);
+        }
+        options
+    }
+
+    /// The options that affect which documents this index constrains, expires, or is used for,
+    /// with unset flags treated as `false`.
+    fn behavior_options(&self) -> BehaviorOptions<'_> {
+        let options = self.options.as_ref();
+        BehaviorOptions {
+            unique: options.and_then(|options| options.unique).unwrap_or(false),
+            sparse: options.and_then(|options| options.sparse).unwrap_or(false),
+            hidden: options.and_then(|options| options.hidden).unwrap_or(false),
+            expire_after: options.and_then(|options| options.expire_after),
+            collation: options.and_then(|options| options.extra.get_document("collation").ok()),
+        }
+    }
+
+    fn partial_filter_expression(&self) -> Option<&Document> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.partial_filter_expression.as_ref())
+    }
+
+    fn is_id_index(&self) -> bool {
+        self.keys.len() == 1 && self.keys.contains_key("_id")
+    }
+}
+
+/// The direction or type a field is indexed by, as given by its value in an index's key pattern.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum IndexDirection {
+    /// An ascending index on the field, e.g. `1`.
+    Asc,
+
+    /// A descending index on the field, e.g. `-1`.
+    Desc,
+
+    /// A [text index](https://docs.mongodb.com/manual/core/index-text/) on the field.
+    Text,
+
+    /// A [2dsphere index](https://docs.mongodb.com/manual/core/2dsphere/) on the field.
+    Geo2dSphere,
+
+    /// A [hashed index](https://docs.mongodb.com/manual/core/index-hashed/) on the field.
+    Hashed,
+
+    /// Any other index type, such as `2d`, with the value from the key pattern.
+    Other(Bson),
+}
+
+impl From<&Bson> for IndexDirection {
+    fn from(value: &Bson) -> Self {
+        match (ascending(value), value.as_str()) {
+            (Some(true), _) => Self::Asc,
+            (Some(false), _) => Self::Desc,
+            (None, Some("text")) => Self::Text,
+            (None, Some("2dsphere")) => Self::Geo2dSphere,
+            (None, Some("hashed")) => Self::Hashed,
+            _ => Self::Other(value.clone()),
+        }
+    }
+}
+
+/// The options compared by [`find_redundant_indexes`] besides the partial filter expression.
+#[derive(PartialEq)]
+struct BehaviorOptions<'a> {
+    unique: bool,
+    sparse: bool,
+    hidden: bool,
+    expire_after: Option<Duration>,
+    collation: Option<&'a Document>,
+}
+
+/// An index that is made redundant by another index whose key pattern it is a prefix of, as
+/// reported by [`find_redundant_indexes`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct RedundantIndex {
+    /// The redundant index.
+    pub index: IndexModel,
+
+    /// The index that can serve any query the redundant index can.
+    pub covered_by: IndexModel,
+}
+
+/// Analyzes the indexes of a collection, e.g. as returned by
+/// [`Collection::list_indexes`](struct.Collection.html#method.list_indexes), and reports the
+/// indexes whose key pattern is a prefix of another index's key pattern.
+///
+/// A prefix only matches if its directions are all the same as, or all the reverse of, the
+/// corresponding directions in the longer key pattern, and both indexes have the same partial
+/// filter expression, collation, and `unique`, `sparse`, `hidden`, and `expire_after` options,
+/// since dropping an index with different ones would change which documents are constrained,
+/// expired, or indexed. A unique index is only made redundant by another unique index with an
+/// equivalent key pattern, as uniqueness over a longer key pattern is a weaker constraint. When two
+/// indexes have equivalent key patterns, only the later one is reported. The `_id` index is never
+/// reported.
+pub fn find_redundant_indexes(indexes: &[IndexModel]) -> Vec<RedundantIndex> {
+    let mut redundant = Vec::new();
+
+    for (i, index) in indexes.iter().enumerate() {
+        if index.is_id_index() {
+            continue;
+        }
+
+        let covered_by = indexes.iter().enumerate().find(|(j, other)| {
+            let longer = other.keys.len() > index.keys.len()
+                || (other.keys.len() == index.keys.len() && *j < i);
+
+            longer
+                && is_key_pattern_prefix(&index.keys, &other.keys)
+                && index.partial_filter_expression() == other.partial_filter_expression()
+                && index.behavior_options() == other.behavior_options()
+                && (!index.behavior_options().unique || other.keys.len() == index.keys.len())
+        });
+
+        if let Some((_, covered_by)) = covered_by {
+            redundant.push(RedundantIndex {
+                index: index.clone(),
+                covered_by: covered_by.clone(),
+            });
+        }
+    }
+
+    redundant
+}
+
+/// Whether `prefix` is a prefix of `keys`, either in the same direction or in the reverse direction
+/// for every field. Non-numeric index types (e.g. "text" or "hashed") must match exactly, and
+/// cannot be traversed in reverse.
+fn is_key_pattern_prefix(prefix: &Document, keys: &Document) -> bool {
+    if prefix.is_empty() || prefix.len() > keys.len() {
+        return false;
+    }
+
+    let mut forward = true;
+    let mut reverse = true;
+
+    for ((name, value), (other_name, other_value)) in prefix.iter().zip(keys.iter()) {
+        if name != other_name {
+            return false;
+        }
+
+        match (ascending(value), ascending(other_value)) {
+            (Some(ascending), Some(other_ascending)) => {
+                forward &= ascending == other_ascending;
+                reverse &= ascending != other_ascending;
+            }
+            _ if value == other_value => reverse = false,
+            _ => return false,
+        }
+    }
+
+    forward || reverse
+}
+
+/// The changes needed to turn one set of indexes into another, as computed by [`index_diff`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct IndexDiff {
+    /// The desired indexes that do not exist yet.
+    pub to_create: Vec<IndexModel>,
+
+    /// The existing indexes that are not desired.
+    pub to_drop: Vec<IndexModel>,
+
+    /// The indexes that exist with the desired key pattern but different options.
+    pub modified: Vec<ModifiedIndex>,
+}
+
+impl IndexDiff {
+    /// Whether the two sets of indexes are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.to_create.is_empty() && self.to_drop.is_empty() && self.modified.is_empty()
+    }
+
+    /// Builds the commands that apply these changes to the collection named `coll_name`, in the
+    /// order they must be run, e.g. with
+    /// [`Database::run_command`](struct.Database.html#method.run_command). Nothing is run.
+    ///
+    /// Each index to drop gets its own `dropIndexes` command, followed by a single
+    /// `createIndexes` command for all of the indexes to create. Modified indexes are dropped and
+    /// re-created with the desired options, except for the `_id` index, which can't be dropped.
+    pub fn commands(&self, coll_name: &str) -> Result<Vec<Document>> {
+        let modified = self
+            .modified
+            .iter()
+            .filter(|modified| !modified.current.is_id_index());
+        let to_drop = self
+            .to_drop
+            .iter()
+            .chain(modified.clone().map(|modified| &modified.current));
+        let to_create = self
+            .to_create
+            .iter()
+            .chain(modified.map(|modified| &modified.desired));
+
+        let mut commands: Vec<Document> = to_drop
+            .map(|index| doc! { "dropIndexes": coll_name, "index": index.resolved_name() })
+            .collect();
+
+        let specs = to_create
+            .map(IndexModel::to_create_indexes_spec)
+            .collect::<Result<Vec<_>>>()?;
+        if !specs.is_empty() {
+            commands.push(doc! { "createIndexes": coll_name, "indexes": specs });
+        }
+
+        Ok(commands)
+    }
+}
+
+/// An index whose options differ from the desired ones, as reported by [`index_diff`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ModifiedIndex {
+    /// The existing index.
+    pub current: IndexModel,
+
+    /// The index as it is desired to be.
+    pub desired: IndexModel,
+}
+
+/// Compares the indexes of a collection, e.g. as returned by
+/// [`Collection::list_indexes`](struct.Collection.html#method.list_indexes), with the desired
+/// indexes, and reports the indexes that need to be created, dropped, or modified.
+///
+/// Indexes are matched by their [resolved name](struct.IndexModel.html#method.resolved_name).
+/// A matched index whose key pattern differs is reported as one to drop and one to create, since
+/// the key pattern of an index can't be changed. A matched index whose options differ, e.g. in
+/// whether it is `hidden`, is reported as modified; the index version and namespace reported by
+/// the server are not compared. The `_id` index is never reported as one to drop.
+pub fn index_diff(current: &[IndexModel], desired: &[IndexModel]) -> IndexDiff {
+    let mut diff = IndexDiff::default();
+
+    for index in current {
+        let name = index.resolved_name();
+        let matching = desired.iter().find(|other| other.resolved_name() == name);
+        match matching {
+            Some(other) if other.create_indexes_keys() == index.create_indexes_keys() => {
+                if other.comparable_options() != index.comparable_options() {
+                    diff.modified.push(ModifiedIndex {
+                        current: index.clone(),
+                        desired: other.clone(),
+                    });
+                }
+            }
+            Some(other) => {
+                diff.to_drop.push(index.clone());
+                diff.to_create.push(other.clone());
+            }
+            None if index.is_id_index() => {}
+            None => diff.to_drop.push(index.clone()),
+        }
+    }
+
+    for index in desired {
+        let name = index.resolved_name();
+        if !current.iter().any(|other| other.resolved_name() == name) {
+            diff.to_create.push(index.clone());
+        }
+    }
+
+    diff
+}
+
+/// Keys the given indexes by their resolved names, failing if two indexes have the same name.
+pub(crate) fn index_map(
+    indexes: impl IntoIterator<Item = IndexModel>,
+) -> Result<HashMap<String, IndexModel>> {
+    let mut map = HashMap::new();
+    for index in indexes {
+        let name = index.resolved_name();
+        if map.contains_key(&name) {
+            return Err(ErrorKind::InvalidResponse {
+                message: format!("multiple indexes named {}", name),
+            }
+            .into());
+        }
+        map.insert(name, index);
+    }
+    Ok(map)
+}
+
+/// The name the server gives an index created without one, e.g. `a_1_b_-1`.
+fn default_index_name(keys: &Document) -> String {
+    keys.iter()
+        .map(|(name, value)| match (bson_util::get_int(value), value) {
+            (Some(direction), _) => format!("{}_{}", name, direction),
+            (None, Bson::String(index_type)) => format!("{}_{}", name, index_type),
+            (None, value) => format!("{}_{}", name, value),
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Whether a key pattern value describes an ascending index on the field, or `None` if it does not
+/// describe a direction at all.
+fn ascending(value: &Bson) -> Option<bool> {
+    bson_util::get_int(value)
+        .filter(|direction| *direction != 0)
+        .map(|direction| direction > 0)
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use crate::{
+    bson::Document,
+    bson_util::{
+        deserialize_duration_option_from_bson_number_seconds,
+        serialize_duration_option_as_int_secs,
+        serialize_u32_option_as_i32,
+    },
+};
+
+/// These are the valid options for specifying an [`IndexModel`](../struct.IndexModel.html).
+/// For more information on these properties, see the
+/// [documentation](https://docs.mongodb.com/manual/reference/command/createIndexes/#definition).
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, TypedBuilder, Serialize, PartialEq)]
+#[builder(field_defaults(default, setter(into)))]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct IndexOptions {
+    /// The name of the index.
+    ///
+    /// If none is specified, the server will generate one from the index's keys.
+    pub name: Option<String>,
+
+    /// The length of time that documents in a TTL index are retained. A value of zero expires
+    /// documents as soon as the value of the indexed date field is reached.
+    ///
+    /// Servers report `NaN` or very large values for indexes that should effectively never expire;
+    /// these are read as the largest number of seconds that can be sent back to the server.
+    ///
+    /// See the [documentation](https://docs.mongodb.com/manual/core/index-ttl/) for more
+    /// information on TTL indexes.
+    #[serde(
+        rename = "expireAfterSeconds",
+        default,
+        serialize_with = "serialize_duration_option_as_int_secs",
+        deserialize_with = "deserialize_duration_option_from_bson_number_seconds"
+    )]
+    pub expire_after: Option<Duration>,
+
+    /// If true, the index rejects documents whose indexed fields duplicate those of an existing
+    /// document.
+    pub unique: Option<bool>,
+
+    /// If true, the index rejects new duplicate entries while existing ones are still allowed,
+    /// which is the first step of converting an index to a unique index. Requires MongoDB 6.0+.
+    ///
+    /// See the [documentation](https://docs.mongodb.com/manual/reference/command/collMod/) for
+    /// more information on converting an index to a unique index.
+    pub prepare_unique: Option<bool>,
+
+    /// If true, the index only references documents that contain the indexed fields.
+    pub sparse: Option<bool>,
+
+    /// If true, the index is hidden from the query planner, which allows the effect of dropping it
+    /// to be evaluated without actually dropping it.
+    ///
+    /// See the [documentation](https://docs.mongodb.com/manual/core/index-hidden/) for more
+    /// information on hidden indexes.
+    pub hidden: Option<bool>,
+
+    /// If specified, the index only references documents that match the filter expression. The
+    /// filter is preserved exactly as the server reports it, including any query operators.
+    ///
+    /// See the [documentation](https://docs.mongodb.com/manual/core/index-partial/) for more
+    /// information on partial indexes.
+    pub partial_filter_expression: Option<Document>,
+
+    /// For text indexes, a document that contains field and weight pairs, where each weight is an
+    /// integer denoting the significance of the field relative to the other indexed fields.
+    pub weights: Option<Document>,
+
+    /// For text indexes, the language that determines the list of stop words and the rules for the
+    /// stemmer and tokenizer.
+    #[serde(rename = "default_language")]
+    pub default_language: Option<String>,
+
+    /// For text indexes, the name of the field, in the collection's documents, that contains the
+    /// override language for the document.
+    #[serde(rename = "language_override")]
+    pub language_override: Option<String>,
+
+    /// For wildcard indexes, the fields to include in or exclude from the index. This is only
+    /// valid when the key pattern is `{ "$**": 1 }`.
+    ///
+    /// See the [documentation](https://docs.mongodb.com/manual/core/index-wildcard/) for more
+    /// information on wildcard indexes.
+    pub wildcard_projection: Option<Document>,
+
+    /// The `2dsphere` index version number.
+    #[serde(
+        rename = "2dsphereIndexVersion",
+        default,
+        serialize_with = "serialize_u32_option_as_i32"
+    )]
+    pub sphere_2d_version: Option<u32>,
+
+    /// For `2d` indexes, the number of precision bits of the geohash value of the location data.
+    #[serde(default, serialize_with = "serialize_u32_option_as_i32")]
+    pub bits: Option<u32>,
+
+    /// For `2d` indexes, the lower inclusive boundary for the longitude and latitude values.
+    pub min: Option<f64>,
+
+    /// For `2d` indexes, the upper inclusive boundary for the longitude and latitude values.
+    pub max: Option<f64>,
+
+    /// The storage engine configuration of the index, keyed by storage engine name, e.g.
+    /// `{ "wiredTiger": { "configString": "..." } }`.
+    pub storage_engine: Option<Document>,
+
+    /// The version of the index format, as reported by the server, e.g. `1` for indexes built by
+    /// MongoDB 3.2 or earlier and `2` for newer ones. This is preserved so that migration tooling
+    /// can find indexes in an old format.
+    #[serde(rename = "v")]
+    pub version: Option<i32>,
+
+    /// Any fields of the index specification that are not modeled by the other options, such as
+    /// options introduced by newer server versions. These are preserved so that an index read from
+    /// `list_indexes` serializes back to the same specification.
+    #[serde(flatten)]
+    pub extra: Document,
+}
@@ -0,0 +1,333 @@
+use std::time::Duration;
+
+use crate::{
+    bson::{doc, Bson, Document},
+    error::ErrorKind,
+    find_redundant_indexes,
+    index::index_map,
+    index_diff,
+    IndexDirection,
+    options::IndexOptions,
+    IndexModel,
+};
+
+fn index(keys: Document) -> IndexModel {
+    IndexModel::builder().keys(keys).build()
+}
+
+fn named(name: &str, keys: Document, hidden: Option<bool>) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(
+            IndexOptions::builder()
+                .name(name.to_string())
+                .hidden(hidden)
+                .build(),
+        )
+        .build()
+}
+
+#[test]
+fn prefix_index_is_redundant() {
+    let indexes = vec![
+        index(doc! { "_id": 1 }),
+        index(doc! { "a": 1 }),
+        index(doc! { "a": 1, "b": 1 }),
+    ];
+
+    let redundant = find_redundant_indexes(&indexes);
+    assert_eq!(redundant.len(), 1);
+    assert_eq!(redundant[0].index.keys, doc! { "a": 1 });
+    assert_eq!(redundant[0].covered_by.keys, doc! { "a": 1, "b": 1 });
+}
+
+#[test]
+fn prefix_comparison_considers_direction() {
+    // Reversing every direction of the prefix still matches.
+    let indexes = vec![index(doc! { "a": 1, "b": -1 }), index(doc! { "a": -1, "b": 1, "c": 1 })];
+    assert_eq!(find_redundant_indexes(&indexes).len(), 1);
+
+    // Reversing only some directions does not.
+    let indexes = vec![index(doc! { "a": 1, "b": 1 }), index(doc! { "a": 1, "b": -1, "c": 1 })];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    // Non-numeric index types must match exactly.
+    let indexes = vec![index(doc! { "a": "hashed" }), index(doc! { "a": 1, "b": 1 })];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    // Equivalent key patterns are only reported once.
+    let indexes = vec![index(doc! { "a": 1 }), index(doc! { "a": 1.0 })];
+    let redundant = find_redundant_indexes(&indexes);
+    assert_eq!(redundant.len(), 1);
+    assert_eq!(redundant[0].index.keys, doc! { "a": 1.0 });
+}
+
+#[test]
+fn prefix_comparison_considers_partial_filter() {
+    let partial = IndexModel::builder()
+        .keys(doc! { "a": 1, "b": 1 })
+        .options(
+            IndexOptions::builder()
+                .partial_filter_expression(doc! { "a": { "$gt": 5 } })
+                .build(),
+        )
+        .build();
+
+    let indexes = vec![index(doc! { "a": 1 }), partial];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+}
+
+#[test]
+fn prefix_comparison_considers_unique() {
+    let unique = |keys: Document| {
+        IndexModel::builder()
+            .keys(keys)
+            .options(IndexOptions::builder().unique(true).build())
+            .build()
+    };
+
+    // Dropping a unique index would drop its constraint.
+    let indexes = vec![unique(doc! { "a": 1 }), index(doc! { "a": 1, "b": 1 })];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    // Uniqueness over a longer key pattern is a weaker constraint.
+    let indexes = vec![unique(doc! { "a": 1 }), unique(doc! { "a": 1, "b": 1 })];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    // A unique index with an equivalent key pattern enforces the same constraint.
+    let indexes = vec![unique(doc! { "a": 1 }), unique(doc! { "a": -1 })];
+    assert_eq!(find_redundant_indexes(&indexes).len(), 1);
+}
+
+#[test]
+fn prefix_comparison_considers_ttl() {
+    let ttl = |keys: Document, secs: u64| {
+        IndexModel::builder()
+            .keys(keys)
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(secs))
+                    .build(),
+            )
+            .build()
+    };
+
+    // Dropping a TTL index would stop documents from expiring.
+    let indexes = vec![ttl(doc! { "a": 1 }, 60), index(doc! { "a": 1, "b": 1 })];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    let indexes = vec![ttl(doc! { "a": 1 }, 60), ttl(doc! { "a": 1 }, 3600)];
+    assert!(find_redundant_indexes(&indexes).is_empty());
+
+    let indexes = vec![ttl(doc! { "a": 1 }, 60), ttl(doc! { "a": 1 }, 60)];
+    assert_eq!(find_redundant_indexes(&indexes).len(), 1);
+}
+
+#[test]
+fn validate_rejects_invalid_combinations() {
+    let unique = || IndexOptions::builder().unique(true).build();
+
+    index(doc! { "a": 1, "b": -1 })
+        .validate()
+        .expect("plain index should be valid");
+    IndexModel::builder()
+        .keys(doc! { "a": 1 })
+        .options(unique())
+        .build()
+        .validate()
+        .expect("unique index should be valid");
+
+    let unique_wildcard = IndexModel::builder()
+        .keys(doc! { "$**": 1 })
+        .options(unique())
+        .build();
+    let error = unique_wildcard.validate().unwrap_err();
+    assert!(matches!(*error.kind, ErrorKind::InvalidArgument { .. }));
+    assert!(IndexModel::builder()
+        .keys(doc! { "a.$**": 1 })
+        .options(unique())
+        .build()
+        .validate()
+        .is_err());
+
+    assert!(IndexModel::builder()
+        .keys(doc! { "a": "hashed" })
+        .options(unique())
+        .build()
+        .validate()
+        .is_err());
+
+    assert!(IndexModel::builder()
+        .keys(doc! { "a": 1 })
+        .options(
+            IndexOptions::builder()
+                .sparse(true)
+                .partial_filter_expression(doc! { "a": { "$exists": true } })
+                .build()
+        )
+        .build()
+        .validate()
+        .is_err());
+
+    assert!(IndexModel::builder()
+        .keys(doc! { "a": 1 })
+        .options(
+            IndexOptions::builder()
+                .wildcard_projection(doc! { "a": 1 })
+                .build()
+        )
+        .build()
+        .validate()
+        .is_err());
+
+    assert!(index(Document::new()).validate().is_err());
+}
+
+#[test]
+fn key_fields_parse_directions() {
+    let keys = doc! {
+        "category": 1,
+        "price": -1.0,
+        "sku": "hashed",
+        "location": "2dsphere",
+        "description": "text",
+        "position": "2d",
+        "rating": 1_i64,
+    };
+
+    assert_eq!(
+        index(keys).key_fields(),
+        vec![
+            ("category".to_string(), IndexDirection::Asc),
+            ("price".to_string(), IndexDirection::Desc),
+            ("sku".to_string(), IndexDirection::Hashed),
+            ("location".to_string(), IndexDirection::Geo2dSphere),
+            ("description".to_string(), IndexDirection::Text),
+            (
+                "position".to_string(),
+                IndexDirection::Other(Bson::String("2d".to_string()))
+            ),
+            ("rating".to_string(), IndexDirection::Asc),
+        ]
+    );
+}
+
+#[test]
+fn summary_lists_notable_options_and_keys() {
+    let unique_partial = IndexModel::builder()
+        .keys(doc! { "name": 1, "age": -1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "age": { "$gt": 18 } })
+                .build(),
+        )
+        .build();
+    assert_eq!(
+        unique_partial.summary(),
+        "name_1_age_-1 (unique, partial) on {name: 1, age: -1}"
+    );
+
+    assert_eq!(index(doc! { "_id": 1 }).summary(), "_id_1 on {_id: 1}");
+
+    let ttl_text = IndexModel::builder()
+        .keys(doc! { "body": "text" })
+        .options(
+            IndexOptions::builder()
+                .name("search".to_string())
+                .expire_after(Duration::from_secs(3600))
+                .build(),
+        )
+        .build();
+    assert_eq!(ttl_text.summary(), "search (ttl 3600s) on {body: \"text\"}");
+}
+
+#[test]
+fn index_map_keyed_by_resolved_name() {
+    let named = IndexModel::builder()
+        .keys(doc! { "a": 1 })
+        .options(IndexOptions::builder().name("by_a".to_string()).build())
+        .build();
+    let indexes = vec![
+        index(doc! { "_id": 1 }),
+        named.clone(),
+        index(doc! { "b": 1, "c": -1 }),
+    ];
+
+    let map = index_map(indexes).unwrap();
+    let mut names: Vec<_> = map.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, vec!["_id_1", "b_1_c_-1", "by_a"]);
+    assert_eq!(map["by_a"], named);
+
+    let duplicate = vec![index(doc! { "a": 1 }), index(doc! { "a": 1 })];
+    let error = index_map(duplicate).unwrap_err();
+    assert!(matches!(*error.kind, ErrorKind::InvalidResponse { .. }));
+}
+
+#[test]
+fn index_diff_reports_changes_by_name() {
+    let current = vec![
+        named("_id_", doc! { "_id": 1 }, None),
+        named("a_1", doc! { "a": 1 }, None),
+        named("b_1", doc! { "b": 1 }, None),
+        named("c", doc! { "c": 1 }, None),
+        named("obsolete", doc! { "d": 1 }, None),
+    ];
+    let desired = vec![
+        // Matches the unnamed desired index by its default name.
+        index(doc! { "a": 1 }),
+        named("b_1", doc! { "b": 1 }, Some(true)),
+        named("c", doc! { "c": -1 }, None),
+        named("new", doc! { "e": 1 }, None),
+    ];
+
+    let diff = index_diff(&current, &desired);
+    assert_eq!(
+        diff.to_create,
+        vec![named("c", doc! { "c": -1 }, None), named("new", doc! { "e": 1 }, None)]
+    );
+    assert_eq!(
+        diff.to_drop,
+        vec![named("c", doc! { "c": 1 }, None), named("obsolete", doc! { "d": 1 }, None)]
+    );
+    assert_eq!(diff.modified.len(), 1);
+    assert_eq!(diff.modified[0].current, current[2]);
+    assert_eq!(diff.modified[0].desired, desired[1]);
+
+    assert!(index_diff(&current, &current).is_empty());
+}
+
+#[test]
+fn index_diff_commands_drop_then_create() {
+    let current = vec![
+        named("_id_", doc! { "_id": 1 }, None),
+        named("b_1", doc! { "b": 1 }, None),
+        named("obsolete", doc! { "d": 1 }, None),
+    ];
+    let desired = vec![
+        named("b_1", doc! { "b": 1 }, Some(true)),
+        named("new", doc! { "e": 1 }, None),
+    ];
+
+    let commands = index_diff(&current, &desired).commands("coll").unwrap();
+    assert_eq!(
+        commands,
+        vec![
+            doc! { "dropIndexes": "coll", "index": "obsolete" },
+            doc! { "dropIndexes": "coll", "index": "b_1" },
+            doc! {
+                "createIndexes": "coll",
+                "indexes": [
+                    { "key": { "e": 1 }, "name": "new" },
+                    { "key": { "b": 1 }, "name": "b_1", "hidden": true },
+                ],
+            },
+        ]
+    );
+
+    assert!(index_diff(&current, &current)
+        .commands("coll")
+        .unwrap()
+        .is_empty());
+}
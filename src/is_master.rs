@@ -8,7 +8,7 @@ use crate::{
         options::{ServerAddress, ServerApi},
         ClusterTime,
     },
-    cmap::{Command, Connection},
+    cmap::{Command, Connection, RawCommandResponse},
     error::{ErrorKind, Result},
     sdam::ServerType,
     selection_criteria::TagSet,
@@ -28,12 +28,14 @@ pub(crate) fn is_master_command(api: Option<&ServerApi>) -> Command {
     command
 }
 
-/// Run the given isMaster command.
+/// Run the given isMaster command, using the given request id for the wire message if one is
+/// provided.
 ///
 /// If the given command is not an isMaster, this function will return an error.
 pub(crate) async fn run_is_master(
     command: Command,
     conn: &mut Connection,
+    request_id: impl Into<Option<i32>>,
 ) -> Result<IsMasterReply> {
     if !command.name.eq_ignore_ascii_case("ismaster") && !command.name.eq_ignore_ascii_case("hello")
     {
@@ -43,10 +45,19 @@ pub(crate) async fn run_is_master(
         .into());
     }
     let start_time = Instant::now();
-    let response = conn.send_command(command, None).await?;
+    let response = conn.send_command(command, request_id).await?;
     let end_time = Instant::now();
 
+    parse_is_master_response(response, Some(end_time.duration_since(start_time)))
+}
+
+/// Parse the server's response to an isMaster command into an `IsMasterReply`.
+pub(crate) fn parse_is_master_response(
+    response: RawCommandResponse,
+    round_trip_time: Option<Duration>,
+) -> Result<IsMasterReply> {
     let server_address = response.source_address().clone();
+    let raw_command_response = response.body()?;
     let basic_response = response.into_document_response()?;
     basic_response.validate()?;
     let cluster_time = basic_response.cluster_time().cloned();
@@ -55,7 +66,8 @@ pub(crate) async fn run_is_master(
     Ok(IsMasterReply {
         server_address,
         command_response,
-        round_trip_time: Some(end_time.duration_since(start_time)),
+        raw_command_response,
+        round_trip_time,
         cluster_time,
     })
 }
@@ -64,6 +76,7 @@ pub(crate) async fn run_is_master(
 pub(crate) struct IsMasterReply {
     pub server_address: ServerAddress,
     pub command_response: IsMasterCommandResponse,
+    pub raw_command_response: Document,
     pub round_trip_time: Option<Duration>,
     pub cluster_time: Option<ClusterTime>,
 }
@@ -98,6 +111,8 @@ pub(crate) struct IsMasterCommandResponse {
     pub max_bson_object_size: i64,
     pub max_write_batch_size: i64,
     pub service_id: Option<ObjectId>,
+    pub hello_ok: Option<bool>,
+    pub connection_id: Option<i64>,
 }
 
 impl PartialEq for IsMasterCommandResponse {
@@ -122,8 +137,13 @@ impl PartialEq for IsMasterCommandResponse {
 }
 
 impl IsMasterCommandResponse {
+    /// Whether the reply carries the `msg: "isdbgrid"` marker sent by mongos.
+    pub(crate) fn is_mongos(&self) -> bool {
+        self.msg.as_deref() == Some("isdbgrid")
+    }
+
     pub(crate) fn server_type(&self) -> ServerType {
-        if self.msg.as_deref() == Some("isdbgrid") {
+        if self.is_mongos() {
             ServerType::Mongos
         } else if self.set_name.is_some() {
             if let Some(true) = self.hidden {
@@ -151,10 +171,15 @@ impl IsMasterCommandResponse {
 #[serde(rename_all = "camelCase")]
 pub(crate) struct LastWrite {
     pub last_write_date: DateTime,
+    pub op_time: Option<OpTime>,
 }
 
+/// The position of an operation in a replica set's oplog.
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(crate) struct OpTime {
-    ts: Timestamp,
-    t: i32,
+    /// The timestamp of the operation.
+    pub ts: Timestamp,
+
+    /// The election term the operation was performed in.
+    pub t: i64,
 }
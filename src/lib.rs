@@ -114,6 +114,7 @@ define_if_single_runtime_enabled! {
     mod db;
     pub mod error;
     pub mod event;
+    mod index;
     mod is_master;
     mod operation;
     pub mod results;
@@ -147,7 +148,18 @@ define_if_single_runtime_enabled! {
         db::Database,
     };
 
-    pub use coll::Namespace;
+    pub use {
+        coll::Namespace,
+        index::{
+            find_redundant_indexes,
+            index_diff,
+            IndexDiff,
+            IndexDirection,
+            IndexModel,
+            ModifiedIndex,
+            RedundantIndex,
+        },
+    };
 }
 
 #[cfg(all(
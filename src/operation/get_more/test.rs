@@ -25,6 +25,9 @@ fn build_test(
         address,
         batch_size,
         max_time,
+        max_batch_size: None,
+        prefetch: false,
+        collection_uuid: None,
     };
     let mut get_more = GetMore::<Document>::new(info);
 
@@ -116,6 +119,9 @@ async fn build_batch_size() {
         id: cursor_id,
         batch_size: Some((std::i32::MAX as u32) + 1),
         max_time: None,
+        max_batch_size: None,
+        prefetch: false,
+        collection_uuid: None,
     };
     let mut op = GetMore::<Document>::new(info);
     assert!(op.build(&StreamDescription::new_testing()).is_err())
@@ -135,6 +141,9 @@ async fn op_selection_criteria() {
         id: 123,
         batch_size: None,
         max_time: None,
+        max_batch_size: None,
+        prefetch: false,
+        collection_uuid: None,
     };
     let get_more = GetMore::<Document>::new(info);
     let server_description = ServerDescription {
@@ -180,6 +189,9 @@ async fn handle_success() {
         id: cursor_id,
         batch_size: None,
         max_time: None,
+        max_batch_size: None,
+        prefetch: false,
+        collection_uuid: None,
     };
     let get_more = GetMore::<Document>::new(info);
 
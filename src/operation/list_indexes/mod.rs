@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod test;
+
+use std::sync::Arc;
+
+use crate::{
+    bson::{doc, Document},
+    cmap::{Command, StreamDescription},
+    cursor::CursorSpecification,
+    error::{ErrorKind, Result},
+    index::IndexModel,
+    operation::{append_options, CursorBody, Operation, Retryability},
+    options::{ListIndexesOptions, ReadPreference, SelectionCriteria},
+    Namespace,
+};
+
+use super::CursorResponse;
+
+/// The minimum wire version (MongoDB 4.4) that supports `$_passthroughToShard`.
+const PASSTHROUGH_TO_SHARD_MIN_WIRE_VERSION: i32 = 9;
+
+#[derive(Debug)]
+pub(crate) struct ListIndexes {
+    ns: Namespace,
+    options: Option<ListIndexesOptions>,
+}
+
+impl ListIndexes {
+    pub(crate) fn new(ns: Namespace, options: Option<ListIndexesOptions>) -> Self {
+        Self { ns, options }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        Self {
+            ns: Namespace {
+                db: String::new(),
+                coll: String::new(),
+            },
+            options: None,
+        }
+    }
+}
+
+impl Operation for ListIndexes {
+    type O = CursorSpecification<IndexModel>;
+    type Command = Document;
+    type Response = CursorResponse<IndexModel>;
+
+    const NAME: &'static str = "listIndexes";
+
+    fn build(&mut self, description: &StreamDescription) -> Result<Command> {
+        let mut body = doc! {
+            Self::NAME: self.ns.coll.clone(),
+        };
+
+        append_options(&mut body, self.options.as_ref())?;
+
+        if let Some(shard) = self.options.as_ref().and_then(|opts| opts.shard.as_ref()) {
+            if description.max_wire_version.unwrap_or(0) < PASSTHROUGH_TO_SHARD_MIN_WIRE_VERSION {
+                return Err(ErrorKind::InvalidArgument {
+                    message: "Listing the indexes of a single shard is not supported on server \
+                              versions < 4.4"
+                        .to_string(),
+                }
+                .into());
+            }
+            body.insert("$_passthroughToShard", doc! { "shard": shard.clone() });
+        }
+
+        Ok(Command::new(
+            Self::NAME.to_string(),
+            self.ns.db.clone(),
+            body,
+        ))
+    }
+
+    fn handle_response(
+        &self,
+        response: CursorBody<IndexModel>,
+        description: &StreamDescription,
+    ) -> Result<Self::O> {
+        let spec = CursorSpecification::new(
+            response.cursor,
+            description.server_address.clone(),
+            self.options.as_ref().and_then(|opts| opts.batch_size),
+            None,
+        );
+
+        let spec = match self.options.as_ref().and_then(|opts| opts.max_batch_size) {
+            Some(max_batch_size) => spec.with_batch_size_growth(max_batch_size),
+            None => spec,
+        };
+
+        let spec = match self.options.as_ref().and_then(|opts| opts.fields) {
+            Some(mask) => spec.with_batch_transform(Arc::new(move |index| mask.apply(index))),
+            None => spec,
+        };
+
+        let prefetch = self
+            .options
+            .as_ref()
+            .and_then(|opts| opts.prefetch)
+            .unwrap_or(false);
+        if prefetch {
+            Ok(spec.with_prefetch())
+        } else {
+            Ok(spec)
+        }
+    }
+
+    fn selection_criteria(&self) -> Option<&SelectionCriteria> {
+        self.options
+            .as_ref()
+            .and_then(|opts| opts.selection_criteria.as_ref())
+            .or_else(|| Some(SelectionCriteria::ReadPreference(ReadPreference::Primary)).as_ref())
+    }
+
+    fn retryability(&self) -> Retryability {
+        Retryability::Read
+    }
+}
@@ -0,0 +1,713 @@
+use std::time::Duration;
+
+use crate::{
+    bson::{doc, spec::BinarySubtype, Binary, Document},
+    bson_util,
+    cmap::StreamDescription,
+    error::ErrorKind,
+    operation::{
+        test::handle_response_test,
+        ListIndexes,
+        Operation,
+    },
+    options::{
+        IndexFieldMask,
+        IndexOptions,
+        ListIndexesOptions,
+        ReadPreference,
+        ReadPreferenceOptions,
+        SelectionCriteria,
+        ServerAddress,
+        TagSet,
+    },
+    IndexModel,
+    Namespace,
+};
+
+/// Parses a `listIndexes` reply whose first batch contains only `spec`, returning the index.
+fn first_index(spec: Document) -> IndexModel {
+    let response = doc! {
+        "cursor": { "id": 0, "ns": "test_db.test_coll", "firstBatch": [spec] },
+        "ok": 1.0
+    };
+    handle_response_test(&ListIndexes::empty(), response)
+        .expect("handle should succeed")
+        .initial_buffer
+        .into_iter()
+        .next()
+        .unwrap()
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn build() {
+    let ns = Namespace {
+        db: "test_db".to_string(),
+        coll: "test_coll".to_string(),
+    };
+
+    let options = ListIndexesOptions::builder()
+        .batch_size(123)
+        .max_time(Duration::from_millis(10))
+        .build();
+    let mut list_indexes = ListIndexes::new(ns, Some(options));
+
+    let mut cmd = list_indexes
+        .build(&StreamDescription::new_testing())
+        .expect("build should succeed");
+    assert_eq!(cmd.name, "listIndexes");
+    assert_eq!(cmd.target_db, "test_db");
+
+    let mut expected_body = doc! {
+        "listIndexes": "test_coll",
+        "cursor": { "batchSize": 123 },
+        "maxTimeMS": 10,
+    };
+    bson_util::sort_document(&mut cmd.body);
+    bson_util::sort_document(&mut expected_body);
+    assert_eq!(cmd.body, expected_body);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn build_not_redacted() {
+    let mut list_indexes = ListIndexes::empty();
+    let cmd = list_indexes
+        .build(&StreamDescription::new_testing())
+        .expect("build should succeed");
+
+    // listIndexes has no sensitive fields, so command monitoring events should include its full
+    // command and reply.
+    assert!(!cmd.should_redact());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn op_selection_criteria() {
+    assert!(ListIndexes::empty()
+        .selection_criteria()
+        .expect("should have criteria")
+        .is_read_pref_primary());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_success() {
+    let list_indexes = ListIndexes::empty();
+
+    let first_batch = vec![
+        doc! { "v": 2, "key": { "_id": 1 }, "name": "_id_" },
+        doc! { "v": 2, "key": { "x": 1 }, "name": "x_1" },
+    ];
+    let response = doc! {
+        "cursor": {
+            "id": 123,
+            "ns": "test_db.test_coll",
+            "firstBatch": bson_util::to_bson_array(&first_batch),
+        },
+        "ok": 1.0
+    };
+
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    assert_eq!(cursor_spec.address(), &ServerAddress::default());
+    assert_eq!(cursor_spec.id(), 123);
+    assert_eq!(cursor_spec.batch_size(), None);
+    assert_eq!(cursor_spec.max_time(), None);
+
+    let indexes: Vec<IndexModel> = cursor_spec.initial_buffer.into_iter().collect();
+    assert_eq!(indexes.len(), 2);
+    assert_eq!(indexes[0].keys, doc! { "_id": 1 });
+    assert_eq!(indexes[1].keys, doc! { "x": 1 });
+    assert_eq!(
+        indexes[1].options.as_ref().and_then(|o| o.name.as_deref()),
+        Some("x_1")
+    );
+    assert!(!indexes[1].is_partial());
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_collection_uuid() {
+    let list_indexes = ListIndexes::empty();
+    let uuid = Binary {
+        subtype: BinarySubtype::Uuid,
+        bytes: vec![7; 16],
+    };
+    let response = doc! {
+        "cursor": {
+            "id": 0,
+            "ns": "test_db.test_coll",
+            "firstBatch": [{ "v": 2, "key": { "_id": 1 }, "name": "_id_" }],
+            "collectionUUID": uuid.clone(),
+        },
+        "ok": 1.0
+    };
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    assert_eq!(cursor_spec.collection_uuid(), Some(&uuid));
+
+    let response = doc! {
+        "cursor": { "id": 0, "ns": "test_db.test_coll", "firstBatch": [] },
+        "ok": 1.0
+    };
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    assert_eq!(cursor_spec.collection_uuid(), None);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_partial_index() {
+    let partial_filter_expression = doc! {
+        "$and": [
+            { "rating": { "$gt": 5 } },
+            { "category": { "$in": ["a", "b"] } },
+        ],
+    };
+
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "cuisine": 1, "name": 1 },
+        "name": "cuisine_1_name_1",
+        "partialFilterExpression": partial_filter_expression.clone(),
+    });
+    assert!(index.is_partial());
+
+    let options = index.options.clone().unwrap();
+    assert_eq!(
+        options.partial_filter_expression,
+        Some(partial_filter_expression.clone())
+    );
+
+    // Serializing the model back out should produce the same expression.
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(
+        serialized.get_document("partialFilterExpression"),
+        Ok(&partial_filter_expression)
+    );
+    assert_eq!(
+        bson::from_document::<IndexModel>(serialized).unwrap(),
+        IndexModel::builder()
+            .keys(doc! { "cuisine": 1, "name": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("cuisine_1_name_1".to_string())
+                    .partial_filter_expression(partial_filter_expression)
+                    .version(2)
+                    .build()
+            )
+            .build()
+    );
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_text_index() {
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "_fts": "text", "_ftsx": 1 },
+        "name": "title_text_body_text",
+        "weights": { "body": 1, "title": 10 },
+        "default_language": "spanish",
+        "language_override": "idioma",
+        "textIndexVersion": 3,
+    });
+    let options = index.options.clone().unwrap();
+    assert_eq!(options.weights, Some(doc! { "body": 1, "title": 10 }));
+    assert_eq!(options.default_language.as_deref(), Some("spanish"));
+    assert_eq!(options.language_override.as_deref(), Some("idioma"));
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(
+        serialized.get_document("weights"),
+        Ok(&doc! { "body": 1, "title": 10 })
+    );
+    assert_eq!(serialized.get_str("default_language"), Ok("spanish"));
+    assert_eq!(serialized.get_str("language_override"), Ok("idioma"));
+
+    // `createIndexes` takes the weighted fields rather than the internal text index keys.
+    let spec = index.to_create_indexes_spec().unwrap();
+    assert_eq!(
+        spec.get_document("key"),
+        Ok(&doc! { "body": "text", "title": "text" })
+    );
+    assert_eq!(spec.get_str("name"), Ok("title_text_body_text"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_wildcard_index() {
+    let wildcard_projection = doc! { "a.b": 1, "c": 1 };
+
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "$**": 1 },
+        "name": "$**_1",
+        "wildcardProjection": wildcard_projection.clone(),
+    });
+    assert_eq!(index.keys, doc! { "$**": 1 });
+    assert_eq!(
+        index.options.as_ref().unwrap().wildcard_projection,
+        Some(wildcard_projection.clone())
+    );
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(serialized.get_document("key"), Ok(&doc! { "$**": 1 }));
+    assert_eq!(
+        serialized.get_document("wildcardProjection"),
+        Ok(&wildcard_projection)
+    );
+    assert_eq!(bson::from_document::<IndexModel>(serialized).unwrap(), index);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_invalid_response() {
+    let list_indexes = ListIndexes::empty();
+
+    let garbled = doc! { "asdfasf": "ASdfasdf" };
+    handle_response_test(&list_indexes, garbled).expect_err("garbled response should fail");
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_ttl_index() {
+    let list_indexes = ListIndexes::empty();
+
+    let response = doc! {
+        "cursor": {
+            "id": 0,
+            "ns": "test_db.test_coll",
+            "firstBatch": [
+                {
+                    "v": 2,
+                    "key": { "createdAt": 1 },
+                    "name": "createdAt_1",
+                    "expireAfterSeconds": 0,
+                },
+                {
+                    "v": 2,
+                    "key": { "updatedAt": 1 },
+                    "name": "updatedAt_1",
+                    "expireAfterSeconds": f64::NAN,
+                },
+            ],
+        },
+        "ok": 1.0
+    };
+
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    let indexes: Vec<IndexModel> = cursor_spec.initial_buffer.into_iter().collect();
+
+    // An expiry of zero is meaningful and must not be dropped.
+    let immediate = &indexes[0];
+    assert_eq!(
+        immediate.options.as_ref().unwrap().expire_after,
+        Some(Duration::from_secs(0))
+    );
+    let serialized: Document = bson::to_document(immediate).unwrap();
+    assert_eq!(serialized.get_i32("expireAfterSeconds"), Ok(0));
+    assert_eq!(
+        bson::from_document::<IndexModel>(serialized).unwrap(),
+        *immediate
+    );
+
+    let never = &indexes[1];
+    assert_eq!(
+        never.options.as_ref().unwrap().expire_after,
+        Some(Duration::from_secs(i64::MAX as u64))
+    );
+    let serialized: Document = bson::to_document(never).unwrap();
+    assert_eq!(serialized.get_i64("expireAfterSeconds"), Ok(i64::MAX));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn read_preference_tags_ordered() {
+    let tag_sets: Vec<TagSet> = vec![
+        vec![("dc".to_string(), "east".to_string())]
+            .into_iter()
+            .collect(),
+        vec![("dc".to_string(), "west".to_string())]
+            .into_iter()
+            .collect(),
+        TagSet::new(),
+    ];
+    let read_pref = ReadPreference::Nearest {
+        options: ReadPreferenceOptions::builder()
+            .tag_sets(tag_sets)
+            .build(),
+    };
+    let options = ListIndexesOptions::builder()
+        .selection_criteria(SelectionCriteria::ReadPreference(read_pref.clone()))
+        .build();
+    let mut list_indexes = ListIndexes::new(
+        Namespace {
+            db: "test_db".to_string(),
+            coll: "test_coll".to_string(),
+        },
+        Some(options),
+    );
+
+    assert_eq!(
+        list_indexes.selection_criteria(),
+        Some(&SelectionCriteria::ReadPreference(read_pref))
+    );
+
+    let cmd = list_indexes
+        .build(&StreamDescription::new_testing())
+        .expect("build should succeed");
+    // The selection criteria must not leak into the command body itself.
+    assert!(!cmd.body.contains_key("readPreference"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_unique_sparse_index() {
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "email": 1 },
+        "name": "email_1",
+        "unique": true,
+        "sparse": true,
+    });
+    let options = index.options.as_ref().unwrap();
+    assert_eq!(options.unique, Some(true));
+    assert_eq!(options.sparse, Some(true));
+    index.validate().expect("unique sparse index should be valid");
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(serialized.get_bool("unique"), Ok(true));
+    assert_eq!(serialized.get_bool("sparse"), Ok(true));
+    assert_eq!(bson::from_document::<IndexModel>(serialized).unwrap(), index);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_geo_indexes() {
+    let list_indexes = ListIndexes::empty();
+
+    let response = doc! {
+        "cursor": {
+            "id": 0,
+            "ns": "test_db.test_coll",
+            "firstBatch": [
+                {
+                    "v": 2,
+                    "key": { "location": "2dsphere" },
+                    "name": "location_2dsphere",
+                    "2dsphereIndexVersion": 3,
+                },
+                {
+                    "v": 2,
+                    "key": { "point": "2d" },
+                    "name": "point_2d",
+                    "bits": 32,
+                    "min": -500.0,
+                    "max": 500.0,
+                },
+            ],
+        },
+        "ok": 1.0
+    };
+
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    let indexes: Vec<IndexModel> = cursor_spec.initial_buffer.into_iter().collect();
+
+    let sphere = &indexes[0];
+    assert_eq!(sphere.keys.get_str("location"), Ok("2dsphere"));
+    assert_eq!(sphere.options.as_ref().unwrap().sphere_2d_version, Some(3));
+
+    let flat = &indexes[1];
+    assert_eq!(flat.keys.get_str("point"), Ok("2d"));
+    let options = flat.options.as_ref().unwrap();
+    assert_eq!(options.bits, Some(32));
+    assert_eq!(options.min, Some(-500.0));
+    assert_eq!(options.max, Some(500.0));
+
+    for index in indexes {
+        let serialized: Document = bson::to_document(&index).unwrap();
+        assert_eq!(bson::from_document::<IndexModel>(serialized).unwrap(), index);
+    }
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_unknown_index_options() {
+    let spec = doc! {
+        "v": 2,
+        "key": { "x": 1 },
+        "name": "x_1",
+        "unique": true,
+        "futureOption": { "enabled": true },
+    };
+
+    let index = first_index(spec.clone());
+    let options = index.options.as_ref().unwrap();
+
+    assert_eq!(options.name.as_deref(), Some("x_1"));
+    assert_eq!(options.unique, Some(true));
+    assert_eq!(options.version, Some(2));
+    // Only the fields that aren't otherwise modeled are kept in `extra`.
+    assert_eq!(options.extra, doc! { "futureOption": { "enabled": true } });
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(serialized.len(), spec.len());
+    for (key, value) in spec.iter() {
+        assert_eq!(serialized.get(key), Some(value), "{}", key);
+    }
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn create_indexes_spec_round_trip() {
+    let index = first_index(doc! {
+        "v": 2,
+        "unique": true,
+        "key": { "cuisine": 1, "rating": -1 },
+        "name": "cuisine_rating",
+        "ns": "test_db.test_coll",
+    });
+    let spec = index.to_create_indexes_spec().unwrap();
+
+    // The key pattern and name come first, and the legacy `ns` field is dropped.
+    assert_eq!(
+        spec,
+        doc! {
+            "key": { "cuisine": 1, "rating": -1 },
+            "name": "cuisine_rating",
+            "unique": true,
+            "v": 2,
+        }
+    );
+
+    // Indexes without a name get the server's default one.
+    let index = IndexModel::builder()
+        .keys(doc! { "cuisine": 1, "rating": -1 })
+        .build();
+    assert_eq!(
+        index.to_create_indexes_spec().unwrap(),
+        doc! { "key": { "cuisine": 1, "rating": -1 }, "name": "cuisine_1_rating_-1" }
+    );
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_batch_size_growth() {
+    let response = doc! {
+        "cursor": {
+            "id": 123,
+            "ns": "test_db.test_coll",
+            "firstBatch": [],
+        },
+        "ok": 1.0
+    };
+
+    // The user's batch size is kept as is unless growth was requested.
+    let options = ListIndexesOptions::builder().batch_size(2).build();
+    let list_indexes = ListIndexes::new(Namespace::empty(), Some(options));
+    let cursor_spec =
+        handle_response_test(&list_indexes, response.clone()).expect("handle should succeed");
+    assert_eq!(cursor_spec.batch_size(), Some(2));
+    assert_eq!(cursor_spec.info.max_batch_size, None);
+
+    let options = ListIndexesOptions::builder()
+        .batch_size(2)
+        .max_batch_size(100)
+        .build();
+    let list_indexes = ListIndexes::new(Namespace::empty(), Some(options));
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    assert_eq!(cursor_spec.batch_size(), Some(2));
+    assert_eq!(cursor_spec.info.max_batch_size, Some(100));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_prepare_unique_index() {
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "email": 1 },
+        "name": "email_1",
+        "prepareUnique": true,
+    });
+    let options = index.options.as_ref().unwrap();
+    assert_eq!(options.prepare_unique, Some(true));
+    assert_eq!(options.unique, None);
+    assert!(options.extra.is_empty());
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(serialized.get_bool("prepareUnique"), Ok(true));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_index_version() {
+    let list_indexes = ListIndexes::empty();
+
+    let response = doc! {
+        "cursor": {
+            "id": 0,
+            "ns": "test_db.test_coll",
+            "firstBatch": [
+                { "v": 1, "key": { "legacy": 1 }, "name": "legacy_1" },
+                { "key": { "unversioned": 1 }, "name": "unversioned_1" },
+            ],
+        },
+        "ok": 1.0
+    };
+
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+    let indexes: Vec<IndexModel> = cursor_spec.initial_buffer.into_iter().collect();
+    let versions: Vec<_> = indexes
+        .iter()
+        .map(|index| index.options.as_ref().and_then(|options| options.version))
+        .collect();
+    assert_eq!(versions, vec![Some(1), None]);
+
+    // The version round-trips, including into the `createIndexes` specification.
+    let serialized: Document = bson::to_document(&indexes[0]).unwrap();
+    assert_eq!(serialized.get_i32("v"), Ok(1));
+    assert_eq!(
+        bson::from_document::<IndexModel>(serialized).unwrap(),
+        indexes[0]
+    );
+    assert_eq!(
+        indexes[0].to_create_indexes_spec().unwrap().get_i32("v"),
+        Ok(1)
+    );
+    assert!(!bson::to_document(&indexes[1]).unwrap().contains_key("v"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn handle_storage_engine_index() {
+    let storage_engine = doc! { "wiredTiger": { "configString": "block_compressor=zstd" } };
+
+    let index = first_index(doc! {
+        "v": 2,
+        "key": { "a": 1 },
+        "name": "a_1",
+        "storageEngine": storage_engine.clone(),
+    });
+    let options = index.options.as_ref().unwrap();
+    assert_eq!(options.storage_engine, Some(storage_engine.clone()));
+    assert!(options.extra.is_empty());
+
+    let serialized: Document = bson::to_document(&index).unwrap();
+    assert_eq!(serialized.get_document("storageEngine"), Ok(&storage_engine));
+    assert_eq!(bson::from_document::<IndexModel>(serialized).unwrap(), index);
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn build_with_shard() {
+    let options = ListIndexesOptions::builder()
+        .shard("shard01".to_string())
+        .build();
+    let mut list_indexes = ListIndexes::new(Namespace::empty(), Some(options));
+
+    let cmd = list_indexes
+        .build(&StreamDescription::with_wire_version(9))
+        .expect("build should succeed");
+    assert_eq!(
+        cmd.body.get_document("$_passthroughToShard"),
+        Ok(&doc! { "shard": "shard01" })
+    );
+    assert!(!cmd.body.contains_key("shard"));
+
+    let error = list_indexes
+        .build(&StreamDescription::with_wire_version(8))
+        .expect_err("build should fail on servers older than 4.4");
+    assert!(matches!(*error.kind, ErrorKind::InvalidArgument { .. }));
+
+    // Without a shard, the listing is not scoped.
+    let cmd = ListIndexes::empty()
+        .build(&StreamDescription::with_wire_version(9))
+        .expect("build should succeed");
+    assert!(!cmd.body.contains_key("$_passthroughToShard"));
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn build_system_namespaces() {
+    for (db, coll) in [("config", "collections"), ("admin", "system.version")].iter() {
+        let ns = Namespace {
+            db: db.to_string(),
+            coll: coll.to_string(),
+        };
+        let mut list_indexes = ListIndexes::new(ns, None);
+
+        let cmd = list_indexes
+            .build(&StreamDescription::new_testing())
+            .expect("build should succeed");
+        assert_eq!(cmd.body.get_str("listIndexes"), Ok(*coll));
+
+        // The command is sent to the system database itself.
+        let serialized: Document = bson::to_document(&cmd).unwrap();
+        assert_eq!(serialized.get_str("$db"), Ok(*db));
+    }
+}
+
+#[test]
+fn prefetch_requires_option() {
+    let prefetching = |prefetch: Option<bool>| {
+        let options = ListIndexesOptions::builder().prefetch(prefetch).build();
+        let list_indexes = ListIndexes::new(
+            Namespace {
+                db: "test_db".to_string(),
+                coll: "test_coll".to_string(),
+            },
+            Some(options),
+        );
+        let response = doc! {
+            "cursor": { "id": 123_i64, "ns": "test_db.test_coll", "firstBatch": [] },
+            "ok": 1.0
+        };
+        handle_response_test(&list_indexes, response)
+            .unwrap()
+            .info
+            .prefetch
+    };
+
+    assert!(prefetching(Some(true)));
+    assert!(!prefetching(Some(false)));
+    assert!(!prefetching(None));
+}
+
+#[test]
+fn handle_names_only_field_mask() {
+    let options = ListIndexesOptions::builder()
+        .fields(IndexFieldMask::builder().name(true).build())
+        .build();
+    let list_indexes = ListIndexes::new(
+        Namespace {
+            db: "test_db".to_string(),
+            coll: "test_coll".to_string(),
+        },
+        Some(options),
+    );
+    let response = doc! {
+        "cursor": {
+            "id": 0,
+            "ns": "test_db.test_coll",
+            "firstBatch": [
+                { "v": 2, "key": { "_id": 1 }, "name": "_id_" },
+                { "v": 2, "key": { "x": 1 }, "name": "x_1", "unique": true, "sparse": true },
+            ],
+        },
+        "ok": 1.0
+    };
+    let cursor_spec = handle_response_test(&list_indexes, response).expect("handle should succeed");
+
+    let indexes: Vec<IndexModel> = cursor_spec.initial_buffer.into_iter().collect();
+    assert_eq!(
+        indexes,
+        vec![
+            IndexModel::builder()
+                .options(IndexOptions::builder().name("_id_".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .options(IndexOptions::builder().name("x_1".to_string()).build())
+                .build(),
+        ]
+    );
+    assert!(cursor_spec.batch_transform.is_some());
+}
@@ -14,6 +14,7 @@ mod get_more;
 mod insert;
 mod list_collections;
 mod list_databases;
+mod list_indexes;
 mod run_command;
 mod update;
 
@@ -22,7 +23,7 @@ mod test;
 
 use std::{collections::VecDeque, fmt::Debug, ops::Deref};
 
-use bson::Timestamp;
+use bson::{Binary, Timestamp};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
@@ -61,6 +62,7 @@ pub(crate) use get_more::GetMore;
 pub(crate) use insert::Insert;
 pub(crate) use list_collections::ListCollections;
 pub(crate) use list_databases::ListDatabases;
+pub(crate) use list_indexes::ListIndexes;
 pub(crate) use run_command::RunCommand;
 pub(crate) use update::Update;
 
@@ -411,6 +413,11 @@ pub(crate) struct CursorInfo<T> {
 
     #[serde(rename = "atClusterTime")]
     pub(crate) at_cluster_time: Option<Timestamp>,
+
+    /// The UUID of the collection the cursor is over, which some server versions report for
+    /// `listIndexes` cursors.
+    #[serde(rename = "collectionUUID")]
+    pub(crate) collection_uuid: Option<Binary>,
 }
 
 #[derive(Debug, PartialEq)]
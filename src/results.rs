@@ -5,6 +5,7 @@ use std::collections::{HashMap, VecDeque};
 use crate::{
     bson::{Bson, Document},
     db::options::CreateCollectionOptions,
+    error::Error,
 };
 
 use bson::Binary;
@@ -161,3 +162,16 @@ pub struct DatabaseSpecification {
     /// is `None`.
     pub shards: Option<Document>,
 }
+
+/// The results of draining a cursor with
+/// [`Cursor::drain_until`](../struct.Cursor.html#method.drain_until).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DrainResult<T> {
+    /// The results retrieved before draining stopped.
+    pub results: Vec<T>,
+
+    /// The error that stopped draining before the cursor was exhausted, if any. If the deadline
+    /// passed, this is an I/O error with a `TimedOut` kind.
+    pub error: Option<Error>,
+}
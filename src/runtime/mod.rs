@@ -18,6 +18,8 @@ pub(crate) use self::{
     resolver::AsyncResolver,
     stream::AsyncStream,
 };
+#[cfg(test)]
+pub(crate) use stream::CustomStream;
 use crate::{error::Result, options::ServerAddress};
 pub(crate) use http::HttpClient;
 #[cfg(feature = "async-std-runtime")]
@@ -33,6 +33,23 @@ pub(crate) enum AsyncStream {
 
     /// A TLS connection over TCP.
     Tls(tokio_rustls::client::TlsStream<AsyncTcpStream>),
+
+    /// A connection over an in-memory transport standing in for a server in tests.
+    #[cfg(test)]
+    Custom(Box<dyn CustomStream>),
+}
+
+/// An in-memory transport that tests can establish a connection over instead of TCP.
+#[cfg(test)]
+pub(crate) trait CustomStream:
+    TokioAsyncRead + TokioAsyncWrite + std::fmt::Debug + Send + Sync + Unpin + 'static
+{
+}
+
+#[cfg(test)]
+impl<T> CustomStream for T where
+    T: TokioAsyncRead + TokioAsyncWrite + std::fmt::Debug + Send + Sync + Unpin + 'static
+{
 }
 
 /// A runtime-agnostic async stream.
@@ -150,7 +167,11 @@ impl AsyncStream {
         // If there are TLS options, wrap the inner stream with rustls.
         match options.tls_options {
             Some(cfg) => {
-                let name = DNSNameRef::try_from_ascii_str(options.address.host()).map_err(|e| {
+                let host = match options.tls_sni_override {
+                    Some(ref host) => host.as_str(),
+                    None => options.address.host(),
+                };
+                let name = DNSNameRef::try_from_ascii_str(host).map_err(|e| {
                     ErrorKind::DnsResolve {
                         message: e.to_string(),
                     }
@@ -166,6 +187,36 @@ impl AsyncStream {
             None => Ok(Self::Tcp(inner)),
         }
     }
+
+    /// The TLS protocol version negotiated with the server, if this is a TLS stream.
+    pub(crate) fn tls_protocol_version(&self) -> Option<rustls::ProtocolVersion> {
+        match self {
+            Self::Tls(ref inner) => {
+                use rustls::Session;
+
+                inner.get_ref().1.get_protocol_version()
+            }
+            _ => None,
+        }
+    }
+
+    /// The DER encoding of the end-entity certificate the server presented, if this is a TLS
+    /// stream.
+    pub(crate) fn peer_certificate(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::Tls(ref inner) => {
+                use rustls::Session;
+
+                inner
+                    .get_ref()
+                    .1
+                    .get_peer_certificates()
+                    .and_then(|certs| certs.into_iter().next())
+                    .map(|cert| cert.0)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl AsyncRead for AsyncStream {
@@ -180,6 +231,10 @@ impl AsyncRead for AsyncStream {
             Self::Tls(ref mut inner) => {
                 tokio_util::io::poll_read_buf(Pin::new(inner), cx, &mut buf)
             }
+            #[cfg(test)]
+            Self::Custom(ref mut inner) => {
+                tokio_util::io::poll_read_buf(Pin::new(inner), cx, &mut buf)
+            }
         }
     }
 }
@@ -194,6 +249,8 @@ impl AsyncWrite for AsyncStream {
             Self::Null => Poll::Ready(Ok(0)),
             Self::Tcp(ref mut inner) => AsyncWrite::poll_write(Pin::new(inner), cx, buf),
             Self::Tls(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(test)]
+            Self::Custom(ref mut inner) => TokioAsyncWrite::poll_write(Pin::new(inner), cx, buf),
         }
     }
 
@@ -202,6 +259,8 @@ impl AsyncWrite for AsyncStream {
             Self::Null => Poll::Ready(Ok(())),
             Self::Tcp(ref mut inner) => AsyncWrite::poll_flush(Pin::new(inner), cx),
             Self::Tls(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(test)]
+            Self::Custom(ref mut inner) => TokioAsyncWrite::poll_flush(Pin::new(inner), cx),
         }
     }
 
@@ -210,6 +269,8 @@ impl AsyncWrite for AsyncStream {
             Self::Null => Poll::Ready(Ok(())),
             Self::Tcp(ref mut inner) => Pin::new(inner).poll_close(cx),
             Self::Tls(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(test)]
+            Self::Custom(ref mut inner) => TokioAsyncWrite::poll_shutdown(Pin::new(inner), cx),
         }
     }
 }
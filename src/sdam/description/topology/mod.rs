@@ -20,6 +20,13 @@ use crate::{
 
 const DEFAULT_HEARTBEAT_FREQUENCY: Duration = Duration::from_secs(10);
 
+/// The smallest positive max staleness permitted by the max staleness spec.
+const SMALLEST_MAX_STALENESS: Duration = Duration::from_secs(90);
+
+/// The interval at which replica set primaries write a no-op when idle, per the max staleness
+/// spec.
+const IDLE_WRITE_PERIOD: Duration = Duration::from_secs(10);
+
 /// The TopologyType type, as described by the SDAM spec.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
 pub(crate) enum TopologyType {
@@ -59,6 +66,10 @@ pub(crate) struct TopologyDescription {
     /// respective supported wire versions.
     compatibility_error: Option<String>,
 
+    /// Describes the mongoses of a sharded topology reporting differing max wire versions, which
+    /// can make the features available to an operation depend on the mongos it's routed to.
+    mongos_version_mismatch: Option<String>,
+
     /// Whether or not this topology supports sessions, and if so, what the logicalSessionTimeout
     /// is for them.
     session_support_status: SessionSupportStatus,
@@ -98,6 +109,7 @@ impl TopologyDescription {
                 .selection_criteria
                 .as_ref()
                 .and_then(|criteria| criteria.max_staleness()),
+            options.heartbeat_freq.unwrap_or(DEFAULT_HEARTBEAT_FREQUENCY),
         )?;
 
         let topology_type = if let Some(true) = options.direct_connection {
@@ -131,6 +143,7 @@ impl TopologyDescription {
             max_set_version: None,
             max_election_id: None,
             compatibility_error: None,
+            mongos_version_mismatch: None,
             session_support_status: SessionSupportStatus::Undetermined,
             transaction_support_status: TransactionSupportStatus::Undetermined,
             cluster_time: None,
@@ -253,6 +266,49 @@ impl TopologyDescription {
         self.compatibility_error.as_ref()
     }
 
+    /// Check whether the mongoses of a sharded topology agree on their max wire version, and
+    /// record a warning describing the divergence if they don't.
+    fn check_mongos_versions(&mut self) {
+        self.mongos_version_mismatch = None;
+
+        if self.topology_type != TopologyType::Sharded {
+            return;
+        }
+
+        let mut versions: Vec<(String, i32)> = self
+            .servers
+            .values()
+            .filter(|server| server.server_type == ServerType::Mongos)
+            .filter_map(|server| {
+                let max_wire_version = server.max_wire_version().ok().flatten()?;
+                Some((server.address.to_string(), max_wire_version))
+            })
+            .collect();
+
+        let diverges = versions
+            .iter()
+            .any(|(_, max_wire_version)| *max_wire_version != versions[0].1);
+        if !diverges {
+            return;
+        }
+
+        versions.sort();
+        let reported: Vec<String> = versions
+            .iter()
+            .map(|(address, max_wire_version)| format!("{} ({})", address, max_wire_version))
+            .collect();
+        self.mongos_version_mismatch = Some(format!(
+            "mongos servers report differing max wire versions: {}",
+            reported.join(", ")
+        ));
+    }
+
+    /// A warning describing how the max wire versions reported by the mongoses of a sharded
+    /// topology diverge, if they do.
+    pub(crate) fn mongos_version_mismatch(&self) -> Option<&String> {
+        self.mongos_version_mismatch.as_ref()
+    }
+
     /// Update the ServerDescription's round trip time based on the rolling average.
     fn update_round_trip_time(&self, server_description: &mut ServerDescription) {
         if let Some(old_rtt) = self
@@ -439,6 +495,9 @@ impl TopologyDescription {
         // Record any compatibility error.
         self.check_compatibility();
 
+        // Record any divergence between the wire versions of the mongoses.
+        self.check_mongos_versions();
+
         Ok(())
     }
 
@@ -777,18 +836,39 @@ pub(crate) struct TopologyDescriptionDiff {
     pub(crate) new_addresses: HashSet<ServerAddress>,
 }
 
-fn verify_max_staleness(max_staleness: Option<Duration>) -> crate::error::Result<()> {
-    verify_max_staleness_inner(max_staleness)
+fn verify_max_staleness(
+    max_staleness: Option<Duration>,
+    heartbeat_frequency: Duration,
+) -> crate::error::Result<()> {
+    verify_max_staleness_inner(max_staleness, heartbeat_frequency)
         .map_err(|s| crate::error::ErrorKind::InvalidArgument { message: s }.into())
 }
 
-fn verify_max_staleness_inner(max_staleness: Option<Duration>) -> std::result::Result<(), String> {
-    if max_staleness
-        .map(|staleness| staleness > Duration::from_secs(0) && staleness < Duration::from_secs(90))
-        .unwrap_or(false)
-    {
+fn verify_max_staleness_inner(
+    max_staleness: Option<Duration>,
+    heartbeat_frequency: Duration,
+) -> std::result::Result<(), String> {
+    // According to the spec, max staleness <= 0 is the same as no max staleness.
+    let max_staleness = match max_staleness {
+        Some(max_staleness) if max_staleness > Duration::from_secs(0) => max_staleness,
+        _ => return Ok(()),
+    };
+
+    if max_staleness < SMALLEST_MAX_STALENESS {
         return Err("max staleness cannot be both positive and below 90 seconds".into());
     }
 
+    // A secondary's staleness can only be estimated to within a heartbeat plus the interval at
+    // which the primary writes a no-op, so a smaller max staleness could never be honored.
+    let minimum = heartbeat_frequency + IDLE_WRITE_PERIOD;
+    if max_staleness < minimum {
+        return Err(format!(
+            "max staleness of {} seconds cannot be less than the heartbeat frequency plus the idle \
+             write period ({} seconds)",
+            max_staleness.as_secs(),
+            minimum.as_secs()
+        ));
+    }
+
     Ok(())
 }
@@ -3,7 +3,11 @@ use std::time::Duration;
 use serde::Deserialize;
 
 use crate::{
-    selection_criteria::{ReadPreference, ReadPreferenceOptions, TagSet},
+    bson::{self, doc},
+    error::ErrorKind,
+    options::{ClientOptions, ServerAddress},
+    sdam::TopologyDescription,
+    selection_criteria::{ReadPreference, ReadPreferenceOptions, SelectionCriteria, TagSet},
     test::run_spec_test,
 };
 
@@ -179,3 +183,37 @@ async fn max_staleness_single() {
 async fn max_staleness_unknown() {
     run_spec_test(&["max-staleness", "Unknown"], run_test).await;
 }
+
+#[test]
+fn nearest_max_staleness_validated() {
+    let nearest = |max_staleness: u64| ReadPreference::Nearest {
+        options: ReadPreferenceOptions::builder()
+            .max_staleness(Duration::from_secs(max_staleness))
+            .build(),
+    };
+    let topology = |read_pref: ReadPreference, heartbeat_freq: Option<Duration>| {
+        let options = ClientOptions::builder()
+            .hosts(vec![ServerAddress::default()])
+            .selection_criteria(SelectionCriteria::ReadPreference(read_pref))
+            .heartbeat_freq(heartbeat_freq)
+            .build();
+        TopologyDescription::new(options)
+    };
+
+    let serialized = bson::to_document(&nearest(120)).unwrap();
+    assert_eq!(
+        serialized,
+        doc! { "mode": "nearest", "maxStalenessSeconds": 120 }
+    );
+    assert!(topology(nearest(120), None).is_ok());
+    assert!(topology(nearest(100), Some(Duration::from_secs(90))).is_ok());
+
+    let error = topology(nearest(60), None).unwrap_err();
+    assert!(matches!(*error.kind, ErrorKind::InvalidArgument { .. }));
+
+    let error = topology(nearest(95), Some(Duration::from_secs(90))).unwrap_err();
+    match *error.kind {
+        ErrorKind::InvalidArgument { message } => assert!(message.contains("100 seconds")),
+        other => panic!("expected InvalidArgument, got {:?}", other),
+    }
+}
@@ -51,6 +51,7 @@ impl TestTopologyDescription {
             max_set_version: None,
             max_election_id: None,
             compatibility_error: None,
+            mongos_version_mismatch: None,
             session_support_status: Default::default(),
             transaction_support_status: Default::default(),
             cluster_time: None,
@@ -94,10 +95,12 @@ impl TestServerDescription {
             command_response.tags = tags;
             command_response.last_write = last_write.map(|last_write| LastWrite {
                 last_write_date: DateTime::from_millis(last_write.last_write_date),
+                op_time: None,
             });
             Ok(IsMasterReply {
                 server_address: server_address.clone(),
                 command_response,
+                raw_command_response: Default::default(),
                 round_trip_time: avg_rtt_ms.map(f64_ms_as_duration),
                 cluster_time: None,
             })
@@ -1,3 +1,4 @@
+mod mongos;
 mod rtt;
 mod sdam;
 
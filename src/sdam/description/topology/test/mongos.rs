@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use crate::{
+    bson::{doc, Bson, Document},
+    cmap::StreamDescription,
+    is_master::{IsMasterCommandResponse, IsMasterReply},
+    operation::{Find, Operation},
+    options::{
+        ClientOptions,
+        ReadPreference,
+        ReadPreferenceOptions,
+        SelectionCriteria,
+        ServerAddress,
+        TagSet,
+    },
+    sdam::{
+        description::{
+            server::ServerDescription,
+            topology::{TopologyDescription, TopologyType},
+        },
+        ServerType,
+    },
+    Namespace,
+};
+
+fn mongos_handshake(address: &ServerAddress, max_wire_version: i32) -> ServerDescription {
+    let command_response = IsMasterCommandResponse {
+        is_writable_primary: Some(true),
+        msg: Some("isdbgrid".to_string()),
+        min_wire_version: Some(0),
+        max_wire_version: Some(max_wire_version),
+        ..Default::default()
+    };
+    let reply = IsMasterReply {
+        server_address: address.clone(),
+        command_response,
+        raw_command_response: Default::default(),
+        round_trip_time: Some(Duration::from_millis(1)),
+        cluster_time: None,
+    };
+    ServerDescription::new(address.clone(), Some(Ok(reply)))
+}
+
+#[test]
+fn mongos_wire_version_divergence_detected() {
+    let a = ServerAddress::parse("a:27017").unwrap();
+    let b = ServerAddress::parse("b:27017").unwrap();
+    let options = ClientOptions::builder()
+        .hosts(vec![a.clone(), b.clone()])
+        .build();
+    let mut topology = TopologyDescription::new(options).unwrap();
+
+    topology.update(mongos_handshake(&a, 9)).unwrap();
+    assert_eq!(topology.topology_type(), TopologyType::Sharded);
+    assert_eq!(topology.mongos_version_mismatch(), None);
+
+    topology.update(mongos_handshake(&b, 13)).unwrap();
+    assert_eq!(
+        topology.mongos_version_mismatch().map(String::as_str),
+        Some("mongos servers report differing max wire versions: a:27017 (9), b:27017 (13)")
+    );
+
+    // The warning clears once the mongoses agree again.
+    topology.update(mongos_handshake(&a, 13)).unwrap();
+    assert_eq!(topology.mongos_version_mismatch(), None);
+}
+
+#[test]
+fn read_preference_tags_sent_to_mongos() {
+    let a = ServerAddress::parse("a:27017").unwrap();
+    let options = ClientOptions::builder().hosts(vec![a.clone()]).build();
+    let mut topology = TopologyDescription::new(options).unwrap();
+    topology.update(mongos_handshake(&a, 13)).unwrap();
+
+    let tag_sets: Vec<TagSet> = vec![
+        vec![("dc".to_string(), "east".to_string())]
+            .into_iter()
+            .collect(),
+        TagSet::new(),
+    ];
+    let criteria = SelectionCriteria::ReadPreference(ReadPreference::Nearest {
+        options: ReadPreferenceOptions::builder()
+            .tag_sets(tag_sets)
+            .build(),
+    });
+
+    let mut find = Find::<Document>::new(Namespace::empty(), None, None);
+    let mut cmd = find.build(&StreamDescription::new_testing()).unwrap();
+    topology.update_command_with_read_pref(ServerType::Mongos, &mut cmd, Some(&criteria));
+
+    // The server expects the tag sets under `tags`, in the order they were given.
+    let serialized = bson::to_document(&cmd).unwrap();
+    let read_pref = serialized.get_document("$readPreference").unwrap();
+    assert_eq!(read_pref.get_str("mode"), Ok("nearest"));
+    assert!(!read_pref.contains_key("tagSets"));
+    assert_eq!(
+        read_pref.get_array("tags").unwrap(),
+        &vec![Bson::Document(doc! { "dc": "east" }), Bson::Document(doc! {})]
+    );
+}
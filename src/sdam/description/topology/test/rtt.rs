@@ -47,6 +47,7 @@ async fn run_test(test_file: TestFile) {
         max_set_version: None,
         max_election_id: None,
         compatibility_error: None,
+        mongos_version_mismatch: None,
         session_support_status: Default::default(),
         transaction_support_status: Default::default(),
         cluster_time: None,
@@ -11,6 +11,7 @@ use crate::{
     error::{BulkWriteFailure, CommandError, Error, ErrorKind},
     is_master::{IsMasterCommandResponse, IsMasterReply, LastWrite},
     options::{ClientOptions, ReadPreference, SelectionCriteria, ServerAddress},
+    srv::OriginalSrvInfo,
     sdam::{
         description::{
             server::{ServerDescription, ServerType},
@@ -105,6 +106,8 @@ impl From<TestIsMasterCommandResponse> for IsMasterCommandResponse {
             max_bson_object_size: test.max_bson_object_size.unwrap_or(1234),
             max_write_batch_size: test.max_write_batch_size.unwrap_or(1234),
             service_id: test.service_id,
+            hello_ok: None,
+            connection_id: None,
         }
     }
 }
@@ -245,6 +248,7 @@ async fn run_test(test_file: TestFile) {
                 Ok(IsMasterReply {
                     server_address: address.clone(),
                     command_response: command_response.into(),
+                    raw_command_response: Default::default(),
                     round_trip_time: Some(Duration::from_millis(1234)), // Doesn't matter for tests.
                     cluster_time: None,
                 })
@@ -546,6 +550,7 @@ async fn pool_cleared_error_does_not_mark_unknown() {
                 Some(Ok(IsMasterReply {
                     server_address: address.clone(),
                     command_response: heartbeat_response,
+                    raw_command_response: Default::default(),
                     round_trip_time: Some(Duration::from_secs(1)),
                     cluster_time: None,
                 })),
@@ -583,3 +588,57 @@ async fn pool_cleared_error_does_not_mark_unknown() {
         ServerType::Standalone
     );
 }
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn connect_failure_removes_srv_host() {
+    let removed = ServerAddress::parse("a:1234").unwrap();
+    let remaining = ServerAddress::parse("b:1234").unwrap();
+    let mut options = ClientOptions::builder()
+        .hosts(vec![removed.clone(), remaining.clone()])
+        .build();
+    options.original_srv_info = Some(OriginalSrvInfo {
+        hostname: "test.mongodb.com".to_string(),
+        min_ttl: Duration::from_secs(60),
+    });
+    let topology = Topology::new_mocked(options);
+
+    let server = topology
+        .get_servers()
+        .await
+        .remove(&removed)
+        .unwrap()
+        .upgrade()
+        .unwrap();
+
+    let phase = || HandshakePhase::PreHello {
+        generation: server.pool.generation(),
+    };
+
+    // A HostUnreachable error reported by the server goes through the usual SDAM error handling,
+    // which leaves the server in the topology.
+    let error: Error = ErrorKind::Command(CommandError {
+        code: 6,
+        code_name: "HostUnreachable".to_string(),
+        message: "host unreachable".to_string(),
+    })
+    .into();
+    assert!(!error.is_connect_failure());
+    topology
+        .handle_application_error(error, phase(), &server)
+        .await;
+    assert!(topology.servers().await.contains(&removed));
+
+    let error: Error = ErrorKind::from(std::io::ErrorKind::ConnectionRefused).into();
+    assert!(error.is_connect_failure());
+    assert!(
+        topology
+            .handle_application_error(error, phase(), &server)
+            .await
+    );
+
+    let servers = topology.servers().await;
+    assert!(!servers.contains(&removed));
+    assert!(servers.contains(&remaining));
+    assert!(topology.get_server_description(&removed).await.is_none());
+}
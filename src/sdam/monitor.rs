@@ -1,6 +1,6 @@
 use std::{
     sync::{Arc, Weak},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use super::{
@@ -12,7 +12,13 @@ use super::{
 use crate::{
     cmap::{Connection, Handshaker},
     error::{Error, Result},
-    is_master::{is_master_command, run_is_master, IsMasterReply},
+    event::sdam::{
+        SdamEventHandler,
+        ServerHeartbeatFailedEvent,
+        ServerHeartbeatStartedEvent,
+        ServerHeartbeatSucceededEvent,
+    },
+    is_master::{run_is_master, IsMasterReply},
     options::{ClientOptions, ServerAddress},
     RUNTIME,
 };
@@ -81,6 +87,9 @@ impl Monitor {
 struct HeartbeatMonitor {
     address: ServerAddress,
     connection: Option<Connection>,
+
+    /// Whether the server acknowledged `helloOk` in the handshake of `connection`.
+    hello_ok: bool,
     handshaker: Handshaker,
     server: Weak<Server>,
     topology: WeakTopology,
@@ -102,6 +111,7 @@ impl HeartbeatMonitor {
             handshaker,
             topology,
             connection: None,
+            hello_ok: false,
         }
     }
 
@@ -187,22 +197,53 @@ impl HeartbeatMonitor {
     async fn perform_is_master(&mut self) -> Result<IsMasterReply> {
         let result = match self.connection {
             Some(ref mut conn) => {
-                let command = is_master_command(self.client_options.server_api.as_ref());
-                run_is_master(command, conn).await
+                let handler = self.client_options.sdam_event_handler.as_ref();
+                let command = self.handshaker.monitoring_command(self.hello_ok);
+                if let Some(handler) = handler {
+                    handler.handle_server_heartbeat_started_event(ServerHeartbeatStartedEvent {
+                        server_address: self.address.clone(),
+                        awaited: false,
+                    });
+                }
+                let start = Instant::now();
+                let result = run_is_master(command, conn, None).await;
+                if let Some(handler) = handler {
+                    emit_heartbeat_result(handler, &self.address, start.elapsed(), &result);
+                }
+                result
             }
+            // The handshaker reports this check itself once connected, so only a failure to
+            // connect is reported here.
             None => {
-                let mut connection = Connection::connect_monitoring(
+                let start = Instant::now();
+                let connection = Connection::connect_monitoring(
                     self.address.clone(),
                     self.client_options.connect_timeout,
                     self.client_options.tls_options(),
+                    self.client_options.tls_sni_override.clone(),
                 )
-                .await?;
+                .await;
+                let mut connection = match connection {
+                    Ok(connection) => connection,
+                    Err(error) => {
+                        let result = Err(error);
+                        if let Some(ref handler) = self.client_options.sdam_event_handler {
+                            handler.handle_server_heartbeat_started_event(
+                                ServerHeartbeatStartedEvent {
+                                    server_address: self.address.clone(),
+                                    awaited: false,
+                                },
+                            );
+                            emit_heartbeat_result(handler, &self.address, start.elapsed(), &result);
+                        }
+                        return result;
+                    }
+                };
 
-                let res = self
-                    .handshaker
-                    .handshake(&mut connection)
-                    .await
-                    .map(|r| r.is_master_reply);
+                let res = self.handshaker.handshake(&mut connection).await.map(|r| {
+                    self.hello_ok = r.hello_ok;
+                    r.is_master_reply
+                });
                 self.connection = Some(connection);
                 res
             }
@@ -220,6 +261,31 @@ impl HeartbeatMonitor {
     }
 }
 
+/// Reports the outcome of a check of the server at `address` that took `duration` to `handler`.
+fn emit_heartbeat_result(
+    handler: &Arc<dyn SdamEventHandler>,
+    address: &ServerAddress,
+    duration: Duration,
+    result: &Result<IsMasterReply>,
+) {
+    match result {
+        Ok(reply) => {
+            handler.handle_server_heartbeat_succeeded_event(ServerHeartbeatSucceededEvent {
+                duration,
+                reply: reply.raw_command_response.clone(),
+                server_address: address.clone(),
+                awaited: false,
+            })
+        }
+        Err(error) => handler.handle_server_heartbeat_failed_event(ServerHeartbeatFailedEvent {
+            duration,
+            failure: error.clone(),
+            server_address: address.clone(),
+            awaited: false,
+        }),
+    }
+}
+
 /// Monitor that listens for updates to a given server generated from operation execution.
 struct UpdateMonitor {
     server: Weak<Server>,
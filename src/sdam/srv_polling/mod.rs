@@ -26,9 +26,16 @@ pub(crate) struct SrvPollingMonitor {
 }
 
 impl SrvPollingMonitor {
+    /// Creates a monitor for the topology, or returns `None` if its hosts should not be polled for:
+    /// either they were not discovered via SRV, or the client is in load balanced mode, where the
+    /// SRV record resolves to the load balancer rather than to the servers behind it.
     pub(crate) fn new(topology: WeakTopology) -> Option<Self> {
         let mut client_options = topology.client_options().clone();
 
+        if client_options.load_balanced == Some(true) {
+            return None;
+        }
+
         let initial_info = match client_options.original_srv_info.take() {
             Some(info) => info,
             None => return None,
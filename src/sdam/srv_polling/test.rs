@@ -102,3 +102,17 @@ async fn timeout_error() {
 async fn no_results() {
     run_test(Ok(Vec::new()), DEFAULT_HOSTS.iter().cloned().collect()).await;
 }
+
+// In load balanced mode, the SRV record resolves to the load balancer, so it is never polled.
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn load_balanced_disables_polling() {
+    let mut options = ClientOptions::new_srv();
+    options.hosts = vec![localhost_test_build_10gen(27017)];
+    let topology = Topology::new_mocked(options.clone());
+    assert!(SrvPollingMonitor::new(topology.downgrade()).is_some());
+
+    options.load_balanced = Some(true);
+    let topology = Topology::new_mocked(options);
+    assert!(SrvPollingMonitor::new(topology.downgrade()).is_none());
+}
@@ -21,7 +21,13 @@ use super::{
 use crate::{
     bson::oid::ObjectId,
     client::ClusterTime,
-    cmap::{conn::ConnectionGeneration, Command, Connection, PoolGeneration},
+    cmap::{
+        conn::ConnectionGeneration,
+        Command,
+        Connection,
+        HandshakeReplyCache,
+        PoolGeneration,
+    },
     error::{load_balanced_mode_mismatch, Error, Result},
     options::{ClientOptions, SelectionCriteria, ServerAddress},
     runtime::HttpClient,
@@ -124,9 +130,10 @@ impl Topology {
     /// Creates a new Topology given the `options`.
     pub(crate) fn new(mut options: ClientOptions) -> Result<Self> {
         let description = TopologyDescription::new(options.clone())?;
-        let is_load_balanced = description.topology_type() == TopologyType::LoadBalanced;
-
         let hosts: Vec<_> = options.hosts.drain(..).collect();
+        options.handshake_reply_cache = options
+            .handshake_reply_cache_window
+            .map(HandshakeReplyCache::new);
 
         let common = Common {
             is_alive: Arc::new(AtomicBool::new(true)),
@@ -161,10 +168,7 @@ impl Topology {
             topology_state.add_new_server(address, options.clone(), &topology.downgrade());
         }
 
-        // When the client is in load balanced mode, it doesn't poll for changes in the SRV record.
-        if !is_load_balanced {
-            SrvPollingMonitor::start(topology.downgrade());
-        }
+        SrvPollingMonitor::start(topology.downgrade());
 
         drop(topology_state);
         Ok(topology)
@@ -276,7 +280,19 @@ impl Topology {
         }
 
         let is_load_balanced = state_lock.description.topology_type() == TopologyType::LoadBalanced;
-        if error.is_state_change_error() {
+        if handshake.is_before_completion()
+            && error.is_connect_failure()
+            && self.common.options.original_srv_info.is_some()
+            && state_lock.servers.len() > 1
+        {
+            // A host that was just removed from the SRV record may still be attempted before the
+            // next rescan, failing to accept connections. Rather than failing selection on it, take
+            // it out of rotation; it will be added back if it reappears in the SRV record. Errors
+            // the server itself reports go through the usual SDAM error handling below.
+            self.remove_server(&server.address, state_lock);
+            server.pool.clear(error, handshake.service_id()).await;
+            true
+        } else if error.is_state_change_error() {
             let updated = is_load_balanced
                 || self
                     .mark_server_as_unknown(error.to_string(), server, state_lock)
@@ -289,10 +305,7 @@ impl Topology {
 
             updated
         } else if error.is_non_timeout_network_error()
-            || (handshake.is_before_completion()
-                && (error.is_auth_error()
-                    || error.is_network_timeout()
-                    || error.is_command_error()))
+            || (handshake.is_before_completion() && should_mark_server_unknown(&error))
         {
             let updated = is_load_balanced
                 || self
@@ -320,6 +333,23 @@ impl Topology {
         updated
     }
 
+    /// Removes a server from the topology, stopping its monitor and dropping its connection pool.
+    fn remove_server(
+        &self,
+        address: &ServerAddress,
+        mut state_lock: RwLockWriteGuard<'_, TopologyState>,
+    ) {
+        let hosts: HashSet<_> = state_lock
+            .servers
+            .keys()
+            .filter(|host| *host != address)
+            .cloned()
+            .collect();
+        state_lock.update_hosts(&hosts, &self.common.options, self.downgrade());
+        drop(state_lock);
+        self.notify_topology_changed();
+    }
+
     /// Marks a server in the cluster as unknown due to the given `error`.
     /// Returns whether the topology changed as a result of the update.
     async fn mark_server_as_unknown(
@@ -431,6 +461,16 @@ impl Topology {
         self.state.read().await.description.session_support_status()
     }
 
+    /// Gets a warning describing how the max wire versions of the mongoses diverge, if they do.
+    pub(crate) async fn mongos_version_mismatch(&self) -> Option<String> {
+        self.state
+            .read()
+            .await
+            .description
+            .mongos_version_mismatch()
+            .cloned()
+    }
+
     /// Gets the latest information on whether transactions are support or not.
     pub(crate) async fn transaction_support_status(&self) -> TransactionSupportStatus {
         self.state
@@ -577,10 +617,28 @@ impl TopologyState {
             self.add_new_server(address.clone(), options.clone(), topology);
         }
 
-        self.servers.retain(|host, _| hosts.contains(host));
+        self.servers.retain(|host, server| {
+            if hosts.contains(host) {
+                return true;
+            }
+            // Operations that already selected the server may still hold a reference to its pool,
+            // so stop it from opening new connections to a server that is no longer monitored.
+            server.pool.drain();
+            false
+        });
     }
 }
 
+/// Whether an error that occurred before a connection's handshake completed should mark its server
+/// as unknown. Per the SDAM spec, this includes authentication errors as well as network errors,
+/// network timeouts, and command errors.
+pub(crate) fn should_mark_server_unknown(error: &Error) -> bool {
+    error.is_auth_error()
+        || error.is_non_timeout_network_error()
+        || error.is_network_timeout()
+        || error.is_command_error()
+}
+
 /// Enum describing a point in time during an operation's execution relative to when the MongoDB
 /// handshake for the conection being used in that operation.
 ///
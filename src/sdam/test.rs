@@ -1,4 +1,5 @@
 use std::{
+    net::TcpListener,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -7,8 +8,11 @@ use bson::{bson, doc};
 use semver::VersionReq;
 use tokio::sync::RwLockWriteGuard;
 
+use super::state::should_mark_server_unknown;
 use crate::{
-    error::ErrorKind,
+    error::{Error, ErrorKind},
+    options::{ClientOptions, ServerAddress},
+    sdam::Topology,
     test::{
         CmapEvent,
         Event,
@@ -329,3 +333,56 @@ async fn auth_error() {
         &vec![bson!({ "_id": 5 }), bson!({ "_id": 6 })]
     );
 }
+
+/// Creates a topology pointed at a local listener and returns whether the listener received a
+/// connection within `timeout`.
+async fn monitor_connects(load_balanced: bool, timeout: Duration) -> bool {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    let mut options = ClientOptions::builder()
+        .hosts(vec![ServerAddress::Tcp {
+            host: "127.0.0.1".to_string(),
+            port: Some(listener.local_addr().unwrap().port()),
+        }])
+        .build();
+    options.load_balanced = Some(load_balanced);
+    let topology = Topology::new(options).unwrap();
+
+    let connected = RUNTIME
+        .timeout(timeout, async {
+            while listener.accept().is_err() {
+                RUNTIME.delay_for(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .is_ok();
+
+    topology.mark_closed();
+    connected
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test(flavor = "multi_thread"))]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+async fn load_balanced_mode_skips_monitoring() {
+    // Outside of load-balanced mode, the monitor connects right away.
+    assert!(monitor_connects(false, Duration::from_secs(10)).await);
+
+    // The load balancer is treated as a single logical server that is never monitored.
+    assert!(!monitor_connects(true, Duration::from_millis(500)).await);
+}
+
+#[test]
+fn handshake_network_error_marks_server_unknown() {
+    let reset: Error = ErrorKind::Io(Arc::new(std::io::ErrorKind::ConnectionReset.into())).into();
+    assert!(should_mark_server_unknown(&reset));
+
+    let timeout: Error = ErrorKind::Io(Arc::new(std::io::ErrorKind::TimedOut.into())).into();
+    assert!(should_mark_server_unknown(&timeout));
+}
+
+#[test]
+fn handshake_auth_error_marks_server_unknown() {
+    let error = Error::authentication_error("SCRAM-SHA-256", "Authentication failed.");
+    assert!(should_mark_server_unknown(&error));
+}
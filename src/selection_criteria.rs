@@ -220,14 +220,16 @@ impl Serialize for ReadPreference {
 pub struct ReadPreferenceOptions {
     /// Specifies which replica set members should be considered for operations. Each tag set will
     /// be checked in order until one or more servers is found with each tag in the set.
+    #[serde(rename(serialize = "tags"), alias = "tags")]
     pub tag_sets: Option<Vec<TagSet>>,
 
     /// Specifies the maximum amount of lag behind the primary that a secondary can be to be
     /// considered for the given operation. Any secondaries lagging behind more than
     /// `max_staleness` will not be considered for the operation.
     ///
-    /// `max_staleness` must be at least 90 seconds. If a `max_staleness` less than 90 seconds is
-    /// specified for an operation, the operation will return an error.
+    /// `max_staleness` must be at least 90 seconds, and at least the client's heartbeat frequency
+    /// plus 10 seconds. If a smaller `max_staleness` is specified for an operation, the operation
+    /// will return an error.
     #[serde(
         rename = "maxStalenessSeconds",
         default,
@@ -373,7 +375,7 @@ pub type TagSet = HashMap<String, String>;
 
 #[cfg(test)]
 mod test {
-    use super::{HedgedReadOptions, ReadPreference, ReadPreferenceOptions};
+    use super::{HedgedReadOptions, ReadPreference, ReadPreferenceOptions, TagSet};
     use crate::bson::doc;
 
     #[test]
@@ -390,4 +392,20 @@ mod test {
             doc! { "mode": "secondary", "hedge": { "enabled": true } }
         );
     }
+
+    #[test]
+    fn tag_sets_round_trip() {
+        let tag_set: TagSet = vec![("dc".to_string(), "ny".to_string())]
+            .into_iter()
+            .collect();
+        let options = ReadPreferenceOptions::builder()
+            .tag_sets(vec![tag_set])
+            .build();
+
+        let doc = bson::to_document(&options).unwrap();
+        assert_eq!(doc, doc! { "tags": [{ "dc": "ny" }] });
+
+        let round_tripped: ReadPreferenceOptions = bson::from_document(doc).unwrap();
+        assert_eq!(round_tripped, options);
+    }
 }
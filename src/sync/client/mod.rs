@@ -1,5 +1,7 @@
 pub mod session;
 
+use futures_util::StreamExt;
+
 use super::{ClientSession, Database};
 use crate::{
     bson::Document,
@@ -14,6 +16,8 @@ use crate::{
     },
     results::DatabaseSpecification,
     Client as AsyncClient,
+    IndexModel,
+    Namespace,
     RUNTIME,
 };
 
@@ -143,10 +147,29 @@ impl Client {
         )
     }
 
+    /// Lists the indexes of every collection in every database in the cluster the Client is
+    /// connected to. See [`mongodb::Client::list_all_indexes`](../struct.Client.html) for how
+    /// `max_concurrency` and errors are handled.
+    pub fn list_all_indexes(
+        &self,
+        max_concurrency: impl Into<Option<usize>>,
+    ) -> impl Iterator<Item = Result<(Namespace, IndexModel)>> {
+        let mut stream = Box::pin(self.async_client.list_all_indexes(max_concurrency));
+        std::iter::from_fn(move || RUNTIME.block_on(stream.next()))
+    }
+
     /// Starts a new `ClientSession`.
     pub fn start_session(&self, options: Option<SessionOptions>) -> Result<ClientSession> {
         RUNTIME
             .block_on(self.async_client.start_session(options))
             .map(Into::into)
     }
+
+    /// Returns a warning if the mongoses the Client is connected to report differing max wire
+    /// versions. See
+    /// [`mongodb::Client::mongos_version_mismatch`](../struct.Client.html#method.mongos_version_mismatch)
+    /// for more details.
+    pub fn mongos_version_mismatch(&self) -> Option<String> {
+        RUNTIME.block_on(self.async_client.mongos_version_mismatch())
+    }
 }
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, fmt::Debug};
+use std::{borrow::Borrow, collections::HashMap, fmt::Debug};
 
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -20,6 +20,7 @@ use crate::{
         FindOptions,
         InsertManyOptions,
         InsertOneOptions,
+        ListIndexesOptions,
         ReadConcern,
         ReplaceOptions,
         SelectionCriteria,
@@ -29,6 +30,7 @@ use crate::{
     },
     results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult},
     Collection as AsyncCollection,
+    IndexModel,
     Namespace,
     RUNTIME,
 };
@@ -132,6 +134,48 @@ impl<T> Collection<T> {
         )
     }
 
+    /// Lists all indexes on this collection.
+    pub fn list_indexes(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+    ) -> Result<Cursor<IndexModel>> {
+        RUNTIME
+            .block_on(self.async_collection.list_indexes(options.into()))
+            .map(Cursor::new)
+    }
+
+    /// Lists all indexes on this collection, keyed by name. Indexes without a `name` are keyed by
+    /// the name the server gives an index created without one.
+    pub fn list_indexes_by_name(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+    ) -> Result<HashMap<String, IndexModel>> {
+        RUNTIME.block_on(self.async_collection.list_indexes_by_name(options.into()))
+    }
+
+    /// Compares the indexes on this collection with `desired` and returns the commands that would
+    /// reconcile them, without running any of them. The commands can be reviewed and then run in
+    /// order with [`Database::run_command`](struct.Database.html#method.run_command). See
+    /// [`index_diff`](../fn.index_diff.html) for how the indexes are compared and
+    /// [`IndexDiff::commands`](../struct.IndexDiff.html#method.commands) for the commands returned.
+    pub fn plan_index_changes(&self, desired: &[IndexModel]) -> Result<Vec<Document>> {
+        RUNTIME.block_on(self.async_collection.plan_index_changes(desired))
+    }
+
+    /// Lists all indexes on this collection using the provided `ClientSession`.
+    pub fn list_indexes_with_session(
+        &self,
+        options: impl Into<Option<ListIndexesOptions>>,
+        session: &mut ClientSession,
+    ) -> Result<SessionCursor<IndexModel>> {
+        RUNTIME
+            .block_on(
+                self.async_collection
+                    .list_indexes_with_session(options.into(), &mut session.async_client_session),
+            )
+            .map(SessionCursor::new)
+    }
+
     /// Runs an aggregation operation.
     ///
     /// See the documentation [here](https://docs.mongodb.com/manual/aggregation/) for more
@@ -1,11 +1,15 @@
+use std::{cmp::Ordering, time::Instant};
+
 use futures_util::stream::StreamExt;
 use serde::de::DeserializeOwned;
 
 use super::ClientSession;
 use crate::{
-    bson::Document,
+    bson::{Binary, Document},
     error::Result,
+    results::DrainResult,
     Cursor as AsyncCursor,
+    IndexModel,
     SessionCursor as AsyncSessionCursor,
     SessionCursorStream,
     RUNTIME,
@@ -83,6 +87,43 @@ where
     pub(crate) fn new(async_cursor: AsyncCursor<T>) -> Self {
         Self { async_cursor }
     }
+
+    /// Retrieves all of the remaining results of this cursor, stopping once `deadline` passes even
+    /// if more batches remain to be fetched.
+    ///
+    /// If draining stops early, the results retrieved so far are returned alongside the error that
+    /// stopped it.
+    pub fn drain_until(&mut self, deadline: Instant) -> DrainResult<T> {
+        RUNTIME.block_on(self.async_cursor.drain_until(deadline))
+    }
+
+    /// Retrieves all of the remaining results of this cursor and returns an iterator over them in
+    /// the order given by `compare`.
+    ///
+    /// Because every result must be retrieved before the first can be yielded, this buffers the
+    /// entire result set in memory rather than streaming it.
+    pub fn sorted_by<F>(self, compare: F) -> Result<std::vec::IntoIter<T>>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        RUNTIME.block_on(self.async_cursor.sorted_by(compare))
+    }
+}
+
+impl Cursor<IndexModel> {
+    /// The UUID of the collection whose indexes this cursor lists, if the server reported it. Since
+    /// a dropped and recreated collection gets a new UUID, this can be used to detect that the
+    /// collection was recreated between two listings of its indexes.
+    pub fn collection_uuid(&self) -> Option<&Binary> {
+        self.async_cursor.collection_uuid()
+    }
+
+    /// Retrieves all of the remaining indexes from this cursor and partitions them into the visible
+    /// indexes and the hidden indexes, in that order. Indexes without a `hidden` flag are
+    /// considered visible.
+    pub fn partition_by_visibility(self) -> Result<(Vec<IndexModel>, Vec<IndexModel>)> {
+        RUNTIME.block_on(self.async_cursor.partition_by_visibility())
+    }
 }
 
 impl<T> Iterator for Cursor<T>
@@ -151,6 +151,88 @@ async fn find() {
     }
 }
 
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+#[function_name::named]
+async fn list_indexes() {
+    let _guard: RwLockReadGuard<()> = LOCK.run_concurrently().await;
+
+    let client = TestClient::new().await;
+    let coll = client
+        .init_db_and_coll(function_name!(), function_name!())
+        .await;
+
+    let partial_filter_expression = doc! { "x": { "$gt": 5 } };
+    client
+        .database(function_name!())
+        .run_command(
+            doc! {
+                "createIndexes": function_name!(),
+                "indexes": [{
+                    "key": { "x": 1 },
+                    "name": "x_1",
+                    "partialFilterExpression": partial_filter_expression.clone(),
+                }],
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let indexes: Vec<_> = coll
+        .list_indexes(None)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(indexes.len(), 2);
+    assert_eq!(indexes[0].keys, doc! { "_id": 1 });
+    assert!(!indexes[0].is_partial());
+    assert_eq!(indexes[1].keys, doc! { "x": 1 });
+    assert_eq!(
+        indexes[1]
+            .options
+            .as_ref()
+            .and_then(|options| options.partial_filter_expression.as_ref()),
+        Some(&partial_filter_expression)
+    );
+}
+
+#[cfg_attr(feature = "tokio-runtime", tokio::test)]
+#[cfg_attr(feature = "async-std-runtime", async_std::test)]
+#[function_name::named]
+async fn list_indexes_command_events() {
+    let _guard: RwLockReadGuard<()> = LOCK.run_concurrently().await;
+
+    let client = EventClient::new().await;
+    let coll = client
+        .init_db_and_coll(function_name!(), function_name!())
+        .await;
+
+    let _: Vec<_> = coll
+        .list_indexes(None)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    let (started, succeeded) = client.get_successful_command_execution("listIndexes");
+    assert_eq!(
+        started.command.get_str("listIndexes"),
+        Ok(function_name!())
+    );
+    assert_eq!(started.db, function_name!());
+
+    let first_batch = succeeded
+        .reply
+        .get_document("cursor")
+        .and_then(|cursor| cursor.get_array("firstBatch"))
+        .unwrap();
+    assert_eq!(first_batch.len(), 1);
+}
+
 #[cfg_attr(feature = "tokio-runtime", tokio::test)]
 #[cfg_attr(feature = "async-std-runtime", async_std::test)]
 #[function_name::named]
@@ -14,6 +14,7 @@ pub(crate) use self::{
     spec::{run_single_test, run_spec_test, run_spec_test_with_path, RunOn, Serverless, Topology},
     util::{
         assert_matches,
+        start_fake_server,
         CmapEvent,
         CommandEvent,
         Event,
@@ -0,0 +1,38 @@
+use std::net::TcpListener;
+
+use crate::{bson::Document, cmap::Message, options::ServerAddress};
+
+/// Starts a fake server on a local port that answers each command it receives on any connection
+/// with the reply returned by `reply`, or closes the connection if `reply` returns `None`. Each
+/// connection is served on its own thread, so `reply` may block without stalling the others.
+pub fn start_fake_server<F>(reply: F) -> ServerAddress
+where
+    F: Fn(&Document) -> Option<Document> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = ServerAddress::Tcp {
+        host: "127.0.0.1".to_string(),
+        port: Some(listener.local_addr().unwrap().port()),
+    };
+
+    let reply = std::sync::Arc::new(reply);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let reply = reply.clone();
+            std::thread::spawn(move || {
+                while let Ok((request_id, command)) = Message::read_command_blocking(&mut stream) {
+                    let reply = match reply(&command) {
+                        Some(reply) => reply,
+                        None => return,
+                    };
+                    if Message::write_reply_blocking(&mut stream, request_id, &reply).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    address
+}
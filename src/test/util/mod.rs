@@ -1,11 +1,13 @@
 mod event;
 mod failpoint;
+mod fake_server;
 mod lock;
 mod matchable;
 
 pub use self::{
     event::{CmapEvent, CommandEvent, Event, EventClient, EventHandler},
     failpoint::{FailCommandOptions, FailPoint, FailPointGuard, FailPointMode},
+    fake_server::start_fake_server,
     lock::TestLock,
     matchable::{assert_matches, Matchable},
 };